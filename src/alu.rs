@@ -0,0 +1,357 @@
+//! Arithmetic and logic instructions, every helper computes the flags as the hardware does.
+
+use crate::cpu::{Cpu, Flags, Reg16};
+
+impl Cpu {
+    /// ADD A,n
+    ///  Flags affected:
+    ///   Z - Set if result is zero.
+    ///   N - Reset.
+    ///   H - Set if carry from bit 3.
+    ///   C - Set if carry from bit 7.
+    pub fn add_a(&mut self, operand: u8) {
+        self.add_a_generic(operand, false);
+    }
+
+    /// ADC A,n
+    /// Add n + carry flag to A, same flags as ADD.
+    pub fn adc_a(&mut self, operand: u8) {
+        self.add_a_generic(operand, true);
+    }
+
+    fn add_a_generic(&mut self, operand: u8, with_carry: bool) {
+        let carry = (with_carry && self.f.contains(Flags::CARRY)) as u8;
+        let result = self.a as u16 + operand as u16 + carry as u16;
+        self.f = Flags::empty();
+        self.set_flag_or_reset(Flags::ZERO, result as u8 == 0);
+        self.set_flag_or_reset(Flags::HALFCARRY, (self.a & 0x0f) + (operand & 0x0f) + carry > 0x0f);
+        self.set_flag_or_reset(Flags::CARRY, result > 0xff);
+        self.a = result as u8;
+    }
+
+    /// SUB n
+    ///  Flags affected:
+    ///   Z - Set if result is zero.
+    ///   N - Set.
+    ///   H - Set if borrow from bit 4.
+    ///   C - Set if borrow.
+    pub fn sub_a(&mut self, operand: u8) {
+        self.a = self.sub_a_generic(operand, false);
+    }
+
+    /// SBC A,n
+    /// Subtract n + carry flag from A, same flags as SUB.
+    pub fn sbc_a(&mut self, operand: u8) {
+        self.a = self.sub_a_generic(operand, true);
+    }
+
+    /// CP n
+    /// Compare A with n. This is basically an A - n
+    ///   subtraction instruction but the results are thrown
+    ///   away
+    pub fn cp_a(&mut self, operand: u8) {
+        self.sub_a_generic(operand, false);
+    }
+
+    fn sub_a_generic(&mut self, operand: u8, with_carry: bool) -> u8 {
+        let carry = (with_carry && self.f.contains(Flags::CARRY)) as u8;
+        let result = self.a.wrapping_sub(operand).wrapping_sub(carry);
+        self.f = Flags::NEGATIVE;
+        self.set_flag_or_reset(Flags::ZERO, result == 0);
+        self.set_flag_or_reset(Flags::HALFCARRY, (self.a & 0x0f) < (operand & 0x0f) + carry);
+        self.set_flag_or_reset(Flags::CARRY, (self.a as u16) < operand as u16 + carry as u16);
+        result
+    }
+
+    /// AND n
+    /// Logical AND n with register A, result in A. H is always set.
+    pub fn and_a(&mut self, operand: u8) {
+        self.a &= operand;
+        self.f = Flags::HALFCARRY;
+        self.set_flag_or_reset(Flags::ZERO, self.a == 0);
+    }
+
+    /// OR n
+    /// Logical OR n with register A, result in A.
+    pub fn or_a(&mut self, operand: u8) {
+        self.a |= operand;
+        self.f = Flags::empty();
+        self.set_flag_or_reset(Flags::ZERO, self.a == 0);
+    }
+
+    /// XOR n
+    /// Logical exclusive OR n with register A, result in A.
+    pub fn xor_a(&mut self, operand: u8) {
+        self.a ^= operand;
+        self.f = Flags::empty();
+        self.set_flag_or_reset(Flags::ZERO, self.a == 0);
+    }
+
+    /// INC n
+    /// Increment n, the carry flag is not affected.
+    pub fn inc(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_add(1);
+        self.set_flag_or_reset(Flags::ZERO, result == 0);
+        self.set_flag_or_reset(Flags::HALFCARRY, value & 0x0f == 0x0f);
+        self.f.remove(Flags::NEGATIVE);
+        result
+    }
+
+    /// DEC n
+    /// Decrement n, the carry flag is not affected.
+    pub fn dec(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        self.set_flag_or_reset(Flags::ZERO, result == 0);
+        self.set_flag_or_reset(Flags::HALFCARRY, value & 0x0f == 0);
+        self.f |= Flags::NEGATIVE;
+        result
+    }
+
+    /// 0x27 DAA
+    /// Decimal adjust register A so it holds a valid BCD number after an addition or a
+    /// subtraction, N is left untouched and H is reset.
+    pub fn daa(&mut self) {
+        let mut correction = 0;
+        let mut carry = false;
+        let subtract = self.f.contains(Flags::NEGATIVE);
+        if self.f.contains(Flags::HALFCARRY) || (!subtract && self.a & 0x0f > 0x09) {
+            correction |= 0x06;
+        }
+        if self.f.contains(Flags::CARRY) || (!subtract && self.a > 0x99) {
+            correction |= 0x60;
+            carry = true;
+        }
+        self.a = if subtract {
+            self.a.wrapping_sub(correction)
+        } else {
+            self.a.wrapping_add(correction)
+        };
+        self.set_flag_or_reset(Flags::ZERO, self.a == 0);
+        self.set_flag_or_reset(Flags::CARRY, carry);
+        self.f.remove(Flags::HALFCARRY);
+    }
+
+    /// 0x2F CPL
+    /// Complement A register.
+    pub fn cpl(&mut self) {
+        self.a = !self.a;
+        self.f |= Flags::NEGATIVE | Flags::HALFCARRY;
+    }
+
+    /// 0x37 SCF
+    /// Set the carry flag.
+    pub fn scf(&mut self) {
+        self.f.remove(Flags::NEGATIVE | Flags::HALFCARRY);
+        self.f |= Flags::CARRY;
+    }
+
+    /// 0x3F CCF
+    /// Complement the carry flag.
+    pub fn ccf(&mut self) {
+        self.f.remove(Flags::NEGATIVE | Flags::HALFCARRY);
+        self.f.toggle(Flags::CARRY);
+    }
+
+    /// Description:
+    ///   Add n to HL.
+    ///  Use with:
+    ///   n = BC,DE,HL,SP
+    ///  Flags affected:
+    ///   Z - Not affected.
+    ///   N - Reset.
+    ///   H - Set if carry from bit 11.
+    ///   C - Set if carry from bit 15.
+    pub fn add_hl(&mut self, reg: Reg16) {
+        let from = self.r16(reg);
+        let hl = self.r_hl();
+        let result = hl as u32 + from as u32;
+        self.set_flag_or_reset(Flags::CARRY, result > 0xffff);
+        self.set_flag_or_reset(Flags::HALFCARRY, (hl & 0x0fff) + (from & 0x0fff) > 0x0fff);
+        self.f.remove(Flags::NEGATIVE);
+        self.w_hl(result as u16);
+    }
+
+    /// 0xE8 ADD SP,e8
+    pub fn add_sp(&mut self, operand: i8) {
+        self.sp = self.sp_plus_e8(operand);
+    }
+
+    /// 0xF8 LD HL,SP+e8
+    pub fn ld_hl_sp(&mut self, operand: i8) {
+        let result = self.sp_plus_e8(operand);
+        self.w_hl(result);
+    }
+
+    /// SP + signed n, used by ADD SP,n and LD HL,SP+n.
+    /// The flags are computed on the lower byte as an unsigned addition.
+    ///  Flags affected:
+    ///   Z - Reset.
+    ///   N - Reset.
+    ///   H - Set if carry from bit 3.
+    ///   C - Set if carry from bit 7.
+    fn sp_plus_e8(&mut self, operand: i8) -> u16 {
+        let sp = self.sp;
+        let offset = operand as u16;
+        self.f = Flags::empty();
+        self.set_flag_or_reset(Flags::HALFCARRY, (sp & 0x0f) + (offset & 0x0f) > 0x0f);
+        self.set_flag_or_reset(Flags::CARRY, (sp & 0xff) + (offset & 0xff) > 0xff);
+        sp.wrapping_add(offset)
+    }
+
+    /// 0x07 RLCA, 0x0F RRCA, 0x17 RLA, 0x1F RRA
+    /// Rotate A, unlike the CB versions the zero flag is always reset.
+    pub fn rotate_a(&mut self, left: bool, through_carry: bool) {
+        self.a = self.rotate(self.a, left, through_carry);
+        self.f.remove(Flags::ZERO);
+    }
+
+    /// CB 0x00-0x1F RLC, RRC, RL, RR
+    /// Rotate n left or right, through the carry flag if `through_carry` is true.
+    pub fn rotate(&mut self, value: u8, left: bool, through_carry: bool) -> u8 {
+        let carry_in = self.f.contains(Flags::CARRY) as u8;
+        let (result, carry_out) = if left {
+            let bit = if through_carry { carry_in } else { value >> 7 };
+            ((value << 1) | bit, value & 0x80 != 0)
+        } else {
+            let bit = if through_carry { carry_in } else { value & 1 };
+            ((value >> 1) | (bit << 7), value & 1 != 0)
+        };
+        self.set_shift_flags(result, carry_out);
+        result
+    }
+
+    /// CB 0x20-0x27 SLA
+    /// Shift n left into carry, bit 0 is reset.
+    pub fn sla(&mut self, value: u8) -> u8 {
+        let result = value << 1;
+        self.set_shift_flags(result, value & 0x80 != 0);
+        result
+    }
+
+    /// CB 0x28-0x2F SRA
+    /// Shift n right into carry, bit 7 doesn't change.
+    pub fn sra(&mut self, value: u8) -> u8 {
+        let result = (value >> 1) | (value & 0x80);
+        self.set_shift_flags(result, value & 1 != 0);
+        result
+    }
+
+    /// CB 0x30-0x37 SWAP
+    /// Swap the upper and lower nibbles of n.
+    pub fn swap(&mut self, value: u8) -> u8 {
+        let result = value.rotate_left(4);
+        self.set_shift_flags(result, false);
+        result
+    }
+
+    /// CB 0x38-0x3F SRL
+    /// Shift n right into carry, bit 7 is reset.
+    pub fn srl(&mut self, value: u8) -> u8 {
+        let result = value >> 1;
+        self.set_shift_flags(result, value & 1 != 0);
+        result
+    }
+
+    /// CB 0x40-0x7F BIT
+    /// Test the bit b of n, the zero flag is set if the bit is 0, the carry is not affected.
+    pub fn bit(&mut self, bit: u8, value: u8) {
+        self.set_flag_or_reset(Flags::ZERO, value & (1 << bit) == 0);
+        self.f.remove(Flags::NEGATIVE);
+        self.f |= Flags::HALFCARRY;
+    }
+
+    #[inline]
+    fn set_shift_flags(&mut self, result: u8, carry: bool) {
+        self.f = Flags::empty();
+        self.set_flag_or_reset(Flags::ZERO, result == 0);
+        self.set_flag_or_reset(Flags::CARRY, carry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daa_after_add_and_sub() {
+        // (A, subtract, operand, A after DAA, flags after DAA)
+        let cases = [
+            (0x15, false, 0x27, 0x42, Flags::empty()),
+            // H set by the addition with a valid lower digit
+            (0x19, false, 0x28, 0x47, Flags::empty()),
+            // C set by the addition with a valid upper digit
+            (0x90, false, 0x90, 0x80, Flags::CARRY),
+            (0x99, false, 0x01, 0x00, Flags::ZERO | Flags::CARRY),
+            (0x99, false, 0x99, 0x98, Flags::CARRY),
+            // After a subtraction only H and C select the correction
+            (0x42, true, 0x15, 0x27, Flags::NEGATIVE),
+            (0x10, true, 0x20, 0x90, Flags::NEGATIVE | Flags::CARRY),
+            (0x00, true, 0x01, 0x99, Flags::NEGATIVE | Flags::CARRY),
+            (0x45, true, 0x45, 0x00, Flags::NEGATIVE | Flags::ZERO),
+        ];
+        for (a, subtract, operand, result, flags) in cases {
+            let mut cpu = Cpu { a, ..Cpu::default() };
+            if subtract {
+                cpu.sub_a(operand);
+            } else {
+                cpu.add_a(operand);
+            }
+            cpu.daa();
+            let op = if subtract { '-' } else { '+' };
+            assert_eq!(cpu.a, result, "{:#04x} {} {:#04x}", a, op, operand);
+            assert_eq!(cpu.f, flags, "{:#04x} {} {:#04x}", a, op, operand);
+        }
+    }
+
+    #[test]
+    fn add_hl_carries() {
+        // (HL, BC, HL after, flags after), Z is set before and must not change
+        let cases = [
+            (0x0FFF, 0x0001, 0x1000, Flags::ZERO | Flags::HALFCARRY),
+            (0x0F00, 0x0100, 0x1000, Flags::ZERO | Flags::HALFCARRY),
+            // The carry from bit 7 doesn't set H
+            (0x00FF, 0x0001, 0x0100, Flags::ZERO),
+            (0x8000, 0x8000, 0x0000, Flags::ZERO | Flags::CARRY),
+            (0xFFFF, 0x0001, 0x0000, Flags::ZERO | Flags::HALFCARRY | Flags::CARRY),
+            (0x1234, 0x0000, 0x1234, Flags::ZERO),
+        ];
+        for (hl, bc, result, flags) in cases {
+            let mut cpu = Cpu { f: Flags::ZERO | Flags::NEGATIVE, ..Cpu::default() };
+            cpu.w_hl(hl);
+            cpu.w16(Reg16::BC, bc);
+            cpu.add_hl(Reg16::BC);
+            assert_eq!(cpu.r_hl(), result, "{:#06x} + {:#06x}", hl, bc);
+            assert_eq!(cpu.f, flags, "{:#06x} + {:#06x}", hl, bc);
+        }
+    }
+
+    #[test]
+    fn sp_plus_e8_flags_from_the_low_byte() {
+        // (SP, e8, result, flags)
+        let cases = [
+            (0x0000, -1, 0xFFFF, Flags::empty()),
+            (0x000F, 1, 0x0010, Flags::HALFCARRY),
+            (0x00FF, 1, 0x0100, Flags::HALFCARRY | Flags::CARRY),
+            // Z is always reset
+            (0xFFFF, 1, 0x0000, Flags::HALFCARRY | Flags::CARRY),
+            // A negative offset is added as an unsigned byte
+            (0x0001, -1, 0x0000, Flags::HALFCARRY | Flags::CARRY),
+            (0x1000, -128, 0x0F80, Flags::empty()),
+            (0x0080, 127, 0x00FF, Flags::empty()),
+            // The carries out of the upper byte don't matter
+            (0x0FF0, 0x10, 0x1000, Flags::CARRY),
+        ];
+        for (sp, e8, result, flags) in cases {
+            let mut cpu = Cpu { sp, f: Flags::all(), ..Cpu::default() };
+            cpu.add_sp(e8);
+            assert_eq!(cpu.sp, result, "ADD SP,e8 {:#06x} + {}", sp, e8);
+            assert_eq!(cpu.f, flags, "ADD SP,e8 {:#06x} + {}", sp, e8);
+
+            let mut cpu = Cpu { sp, f: Flags::all(), ..Cpu::default() };
+            cpu.ld_hl_sp(e8);
+            assert_eq!(cpu.r_hl(), result, "LD HL,SP+e8 {:#06x} + {}", sp, e8);
+            assert_eq!(cpu.sp, sp, "LD HL,SP+e8 {:#06x} + {}", sp, e8);
+            assert_eq!(cpu.f, flags, "LD HL,SP+e8 {:#06x} + {}", sp, e8);
+        }
+    }
+}
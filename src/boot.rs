@@ -0,0 +1,153 @@
+//! Boot roms of the different models and the state they leave the console in.
+
+use crate::cartridge::CgbSupport;
+use crate::cpu::{Cpu, Reg16};
+use crate::mmu::MMU;
+
+/// GameBoy models, they differ by their boot rom and the state it leaves behind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Model {
+    /// Original GameBoy
+    Dmg,
+    /// GameBoy Pocket
+    Mgb,
+    /// Super GameBoy
+    Sgb,
+    /// GameBoy Color
+    Cgb,
+}
+
+impl Model {
+    /// Size of the boot rom image, the CGB one is mapped at 0x0000-0x00FF and 0x0200-0x08FF.
+    pub fn boot_rom_size(self) -> usize {
+        match self {
+            Model::Dmg | Model::Mgb | Model::Sgb => 0x100,
+            Model::Cgb => 0x900,
+        }
+    }
+
+    /// The model has the CGB hardware, whatever the cartridge.
+    pub fn is_cgb(self) -> bool {
+        self == Model::Cgb
+    }
+}
+
+/// The DMG boot rom, the only one built in.
+pub static BIOS: [u8;256] = [
+0x31, 0xFE, 0xFF, 0xAF, 0x21, 0xFF, 0x9F, 0x32, 0xCB, 0x7C, 0x20, 0xFB, 0x21, 0x26, 0xFF, 0x0E,
+0x11, 0x3E, 0x80, 0x32, 0xE2, 0x0C, 0x3E, 0xF3, 0xE2, 0x32, 0x3E, 0x77, 0x77, 0x3E, 0xFC, 0xE0,
+0x47, 0x11, 0x04, 0x01, 0x21, 0x10, 0x80, 0x1A, 0xCD, 0x95, 0x00, 0xCD, 0x96, 0x00, 0x13, 0x7B,
+0xFE, 0x34, 0x20, 0xF3, 0x11, 0xD8, 0x00, 0x06, 0x08, 0x1A, 0x13, 0x22, 0x23, 0x05, 0x20, 0xF9,
+0x3E, 0x19, 0xEA, 0x10, 0x99, 0x21, 0x2F, 0x99, 0x0E, 0x0C, 0x3D, 0x28, 0x08, 0x32, 0x0D, 0x20,
+0xF9, 0x2E, 0x0F, 0x18, 0xF3, 0x67, 0x3E, 0x64, 0x57, 0xE0, 0x42, 0x3E, 0x91, 0xE0, 0x40, 0x04,
+0x1E, 0x02, 0x0E, 0x0C, 0xF0, 0x44, 0xFE, 0x90, 0x20, 0xFA, 0x0D, 0x20, 0xF7, 0x1D, 0x20, 0xF2,
+0x0E, 0x13, 0x24, 0x7C, 0x1E, 0x83, 0xFE, 0x62, 0x28, 0x06, 0x1E, 0xC1, 0xFE, 0x64, 0x20, 0x06,
+0x7B, 0xE2, 0x0C, 0x3E, 0x87, 0xF2, 0xF0, 0x42, 0x90, 0xE0, 0x42, 0x15, 0x20, 0xD2, 0x05, 0x20,
+0x4F, 0x16, 0x20, 0x18, 0xCB, 0x4F, 0x06, 0x04, 0xC5, 0xCB, 0x11, 0x17, 0xC1, 0xCB, 0x11, 0x17,
+0x05, 0x20, 0xF5, 0x22, 0x23, 0x22, 0x23, 0xC9, 0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B,
+0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D, 0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E,
+0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99, 0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC,
+0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E, 0x3c, 0x42, 0xB9, 0xA5, 0xB9, 0xA5, 0x42, 0x4C,
+0x21, 0x04, 0x01, 0x11, 0xA8, 0x00, 0x1A, 0x13, 0xBE, 0x20, 0xFE, 0x23, 0x7D, 0xFE, 0x34, 0x20,
+0xF5, 0x06, 0x19, 0x78, 0x86, 0x23, 0x05, 0x20, 0xFB, 0x86, 0x20, 0xFE, 0x3E, 0x01, 0xE0, 0x50
+];
+
+
+/// IO registers as left by the DMG, MGB and SGB boot roms, LY, STAT and DMA are not written as
+/// they are driven by the hardware.
+const DMG_IO: &[(u16, u8)] = &[
+    (0xFF00, 0xCF), (0xFF01, 0x00), (0xFF02, 0x7E), (0xFF05, 0x00), (0xFF06, 0x00), (0xFF07, 0xF8),
+    (0xFF0F, 0xE1), (0xFF10, 0x80), (0xFF11, 0xBF), (0xFF12, 0xF3), (0xFF13, 0xFF), (0xFF14, 0xBF),
+    (0xFF16, 0x3F), (0xFF17, 0x00), (0xFF18, 0xFF), (0xFF19, 0xBF), (0xFF1A, 0x7F), (0xFF1B, 0xFF),
+    (0xFF1C, 0x9F), (0xFF1D, 0xFF), (0xFF1E, 0xBF), (0xFF20, 0xFF), (0xFF21, 0x00), (0xFF22, 0x00),
+    (0xFF23, 0xBF), (0xFF24, 0x77), (0xFF25, 0xF3), (0xFF26, 0xF1), (0xFF40, 0x91), (0xFF42, 0x00),
+    (0xFF43, 0x00), (0xFF45, 0x00), (0xFF47, 0xFC), (0xFF4A, 0x00), (0xFF4B, 0x00), (0xFFFF, 0x00),
+];
+
+/// IO registers as left by the CGB boot rom, the serial clock speed bit of SC is set. The CGB
+/// only registers besides KEY1 are not emulated.
+const CGB_IO: &[(u16, u8)] = &[
+    (0xFF00, 0xCF), (0xFF01, 0x00), (0xFF02, 0x7F), (0xFF05, 0x00), (0xFF06, 0x00), (0xFF07, 0xF8),
+    (0xFF0F, 0xE1), (0xFF10, 0x80), (0xFF11, 0xBF), (0xFF12, 0xF3), (0xFF13, 0xFF), (0xFF14, 0xBF),
+    (0xFF16, 0x3F), (0xFF17, 0x00), (0xFF18, 0xFF), (0xFF19, 0xBF), (0xFF1A, 0x7F), (0xFF1B, 0xFF),
+    (0xFF1C, 0x9F), (0xFF1D, 0xFF), (0xFF1E, 0xBF), (0xFF20, 0xFF), (0xFF21, 0x00), (0xFF22, 0x00),
+    (0xFF23, 0xBF), (0xFF24, 0x77), (0xFF25, 0xF3), (0xFF26, 0xF1), (0xFF40, 0x91), (0xFF42, 0x00),
+    (0xFF43, 0x00), (0xFF45, 0x00), (0xFF47, 0xFC), (0xFF4A, 0x00), (0xFF4B, 0x00), (0xFF4D, 0x7E),
+    (0xFFFF, 0x00),
+];
+
+/// Put the console in the state the boot rom of `model` leaves it in, then unmap the boot rom.
+/// See https://gbdev.io/pandocs/Power_Up_Sequence.html
+pub fn skip_boot(cpu: &mut Cpu, mmu: &mut MMU, model: Model) {
+    let (header_checksum, cgb_cartridge) = mmu.cartridge.as_ref().map_or((0, false), |c| {
+        (c.header.header_checksum, c.header.cgb != CgbSupport::None)
+    });
+    // The DMG and MGB boot roms leave H and C set unless the header checksum is 0
+    let dmg_flags = if header_checksum == 0 { 0x80 } else { 0xB0 };
+    let (af, bc, de, hl) = match model {
+        Model::Dmg => (0x0100 | dmg_flags, 0x0013, 0x00D8, 0x014D),
+        Model::Mgb => (0xFF00 | dmg_flags, 0x0013, 0x00D8, 0x014D),
+        Model::Sgb => (0x0100, 0x0014, 0x0000, 0xC060),
+        Model::Cgb if cgb_cartridge => (0x1180, 0x0000, 0xFF56, 0x000D),
+        // B = 0x43 tells the games checking for it that a CGB runs a DMG cartridge
+        Model::Cgb => (0x1180, 0x4300, 0x0008, 0x007C),
+    };
+    cpu.w16(Reg16::AF, af);
+    cpu.w16(Reg16::BC, bc);
+    cpu.w16(Reg16::DE, de);
+    cpu.w16(Reg16::HL, hl);
+    cpu.sp = 0xFFFE;
+    cpu.pc = 0x0100;
+
+    let io = if model.is_cgb() { CGB_IO } else { DMG_IO };
+    for (addr, val) in io {
+        mmu.wb(*addr, *val);
+    }
+    if model == Model::Sgb {
+        // The SGB boot rom doesn't leave the sound on
+        mmu.wb(0xFF26, 0xF0);
+    }
+    // DIV is only known for the DMG and the MGB, it depends on the boot duration on the others
+    if matches!(model, Model::Dmg | Model::Mgb) {
+        mmu.timer.set_counter(0xABCC);
+    }
+    mmu.unmap_boot_rom();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::CartridgeHeader;
+    use crate::mmu::SC_ADDR;
+
+    /// ROM only image with the CGB flag set in its header.
+    fn rom(cgb_flag: u8) -> Vec<u8> {
+        let mut rom = vec![0; 0x8000];
+        rom[0x143] = cgb_flag;
+        rom[0x14D] = CartridgeHeader::compute_header_checksum(&rom);
+        rom
+    }
+
+    #[test]
+    fn state_after_boot() {
+        // (model, CGB flag, AF, BC, SC)
+        let cases = [
+            (Model::Dmg, 0x00, 0x01B0, 0x0013, 0x7E),
+            (Model::Sgb, 0x00, 0x0100, 0x0014, 0x7E),
+            // The CGB tells the DMG cartridges apart through B
+            (Model::Cgb, 0x00, 0x1180, 0x4300, 0x7F),
+            (Model::Cgb, 0x80, 0x1180, 0x0000, 0x7F),
+            (Model::Cgb, 0xC0, 0x1180, 0x0000, 0x7F),
+        ];
+        for (model, cgb_flag, af, bc, sc) in cases {
+            let mut mmu = MMU::default();
+            mmu.load_rom(&rom(cgb_flag)).unwrap();
+            let mut cpu = Cpu::default();
+            skip_boot(&mut cpu, &mut mmu, model);
+            assert_eq!(cpu.r16(Reg16::AF), af, "{:?}, CGB flag {:#04x}", model, cgb_flag);
+            assert_eq!(cpu.r16(Reg16::BC), bc, "{:?}, CGB flag {:#04x}", model, cgb_flag);
+            assert_eq!(mmu.rb(SC_ADDR), sc, "{:?}, CGB flag {:#04x}", model, cgb_flag);
+            assert_eq!(cpu.pc, 0x0100, "{:?}, CGB flag {:#04x}", model, cgb_flag);
+        }
+    }
+}
@@ -0,0 +1,550 @@
+//! Cartridge image and its header (0x0100-0x014F).
+
+use log::*;
+
+use crate::error::EmulationError;
+use crate::input::{ImageSource, Tilt};
+use crate::mbc::{
+    HuC1, HuC3, Mbc, Mbc1, Mbc2, Mbc3, Mbc5, Mbc6, Mbc7, Mmm01, PocketCamera, RomOnly, RtcClock, Tama5,
+    MBC2_RAM_SIZE, MBC6_RAM_SIZE, MBC7_EEPROM_SIZE, TAMA5_RAM_SIZE,
+};
+
+/// Size of a switchable ROM bank.
+pub const ROM_BANK_SIZE: usize = 0x4000;
+/// Size of a switchable external RAM bank.
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+const TITLE: usize = 0x134;
+const MANUFACTURER_CODE: usize = 0x13F;
+const CGB_FLAG: usize = 0x143;
+const NEW_LICENSEE_CODE: usize = 0x144;
+const SGB_FLAG: usize = 0x146;
+const CARTRIDGE_TYPE: usize = 0x147;
+const ROM_SIZE: usize = 0x148;
+const RAM_SIZE: usize = 0x149;
+const DESTINATION_CODE: usize = 0x14A;
+const OLD_LICENSEE_CODE: usize = 0x14B;
+const VERSION: usize = 0x14C;
+const HEADER_CHECKSUM: usize = 0x14D;
+const GLOBAL_CHECKSUM: usize = 0x14E;
+/// First byte after the header.
+pub const HEADER_END: usize = 0x150;
+
+/// Memory bank controller wired in the cartridge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mapper {
+    RomOnly,
+    Mbc1,
+    Mbc2,
+    Mmm01,
+    Mbc3,
+    Mbc5,
+    Mbc6,
+    Mbc7,
+    PocketCamera,
+    Tama5,
+    HuC3,
+    HuC1,
+}
+
+/// Decoded cartridge type byte (0x0147).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CartridgeType {
+    pub code: u8,
+    pub mapper: Mapper,
+    pub ram: bool,
+    pub battery: bool,
+    pub timer: bool,
+    pub rumble: bool,
+    pub sensor: bool,
+}
+
+impl CartridgeType {
+    /// Decode the cartridge type byte, None for the codes no cartridge uses.
+    pub fn from_code(code: u8) -> Option<Self> {
+        use Mapper::*;
+        let (mapper, ram, battery, timer, rumble, sensor) = match code {
+            0x00 => (RomOnly, false, false, false, false, false),
+            0x01 => (Mbc1, false, false, false, false, false),
+            0x02 => (Mbc1, true, false, false, false, false),
+            0x03 => (Mbc1, true, true, false, false, false),
+            0x05 => (Mbc2, false, false, false, false, false),
+            0x06 => (Mbc2, false, true, false, false, false),
+            0x08 => (RomOnly, true, false, false, false, false),
+            0x09 => (RomOnly, true, true, false, false, false),
+            0x0B => (Mmm01, false, false, false, false, false),
+            0x0C => (Mmm01, true, false, false, false, false),
+            0x0D => (Mmm01, true, true, false, false, false),
+            0x0F => (Mbc3, false, true, true, false, false),
+            0x10 => (Mbc3, true, true, true, false, false),
+            0x11 => (Mbc3, false, false, false, false, false),
+            0x12 => (Mbc3, true, false, false, false, false),
+            0x13 => (Mbc3, true, true, false, false, false),
+            0x19 => (Mbc5, false, false, false, false, false),
+            0x1A => (Mbc5, true, false, false, false, false),
+            0x1B => (Mbc5, true, true, false, false, false),
+            0x1C => (Mbc5, false, false, false, true, false),
+            0x1D => (Mbc5, true, false, false, true, false),
+            0x1E => (Mbc5, true, true, false, true, false),
+            0x20 => (Mbc6, true, true, false, false, false),
+            0x22 => (Mbc7, true, true, false, true, true),
+            0xFC => (PocketCamera, true, true, false, false, false),
+            // The TAMA6 clock chip of the TAMA5 is not emulated
+            0xFD => (Tama5, true, true, false, false, false),
+            0xFE => (HuC3, true, true, true, false, false),
+            0xFF => (HuC1, true, true, false, false, false),
+            _ => return None,
+        };
+        Some(CartridgeType { code, mapper, ram, battery, timer, rumble, sensor })
+    }
+}
+
+/// CGB support declared at 0x0143.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CgbSupport {
+    /// DMG cartridge
+    None,
+    /// Works on both DMG and CGB (0x80)
+    Enhanced,
+    /// Only works on CGB (0xC0)
+    Only,
+}
+
+/// Destination code at 0x014A.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Destination {
+    Japanese,
+    Overseas,
+}
+
+/// Cartridge header, see https://gbdev.io/pandocs/The_Cartridge_Header.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CartridgeHeader {
+    pub title: String,
+    /// Only present on the late cartridges, inside the title area
+    pub manufacturer_code: Option<String>,
+    pub cgb: CgbSupport,
+    pub new_licensee_code: String,
+    pub sgb: bool,
+    pub cartridge_type: CartridgeType,
+    pub rom_size_code: u8,
+    pub ram_size_code: u8,
+    /// ROM size in bytes
+    pub rom_size: usize,
+    /// External RAM size in bytes
+    pub ram_size: usize,
+    pub destination: Destination,
+    pub old_licensee_code: u8,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+}
+
+impl CartridgeHeader {
+    /// Parse the header of the ROM image. Values the hardware can't handle and a header checksum
+    /// mismatch are errors, a global checksum mismatch is only logged as it is never checked.
+    pub fn parse(rom: &[u8]) -> Result<Self, EmulationError> {
+        if rom.len() < HEADER_END {
+            return Err(EmulationError::RomTooSmall(rom.len()));
+        }
+        let cgb = match rom[CGB_FLAG] {
+            0xC0 => CgbSupport::Only,
+            0x80 => CgbSupport::Enhanced,
+            _ => CgbSupport::None,
+        };
+        // The CGB flag and the manufacturer code were carved out of the end of the title
+        let manufacturer = &rom[MANUFACTURER_CODE..CGB_FLAG];
+        let manufacturer_code = if cgb != CgbSupport::None
+            && manufacturer.iter().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        {
+            Some(ascii(manufacturer))
+        } else {
+            None
+        };
+        let title_end = match (&manufacturer_code, cgb) {
+            (Some(_), _) => MANUFACTURER_CODE,
+            (None, CgbSupport::None) => CGB_FLAG + 1,
+            (None, _) => CGB_FLAG,
+        };
+
+        let cartridge_type = CartridgeType::from_code(rom[CARTRIDGE_TYPE])
+            .ok_or(EmulationError::UnsupportedCartridge(rom[CARTRIDGE_TYPE]))?;
+        let rom_size_code = rom[ROM_SIZE];
+        let rom_size = match rom_size_code {
+            0x00..=0x08 => 0x8000 << rom_size_code,
+            0x52 => 72 * ROM_BANK_SIZE,
+            0x53 => 80 * ROM_BANK_SIZE,
+            0x54 => 96 * ROM_BANK_SIZE,
+            _ => return Err(EmulationError::InvalidHeader { field: "rom size", value: rom_size_code }),
+        };
+        let ram_size_code = rom[RAM_SIZE];
+        let ram_size = match ram_size_code {
+            0x00 => 0,
+            0x01 => 0x800,
+            0x02 => RAM_BANK_SIZE,
+            0x03 => 4 * RAM_BANK_SIZE,
+            0x04 => 16 * RAM_BANK_SIZE,
+            0x05 => 8 * RAM_BANK_SIZE,
+            _ => return Err(EmulationError::InvalidHeader { field: "ram size", value: ram_size_code }),
+        };
+
+        let header = CartridgeHeader {
+            title: ascii(&rom[TITLE..title_end]),
+            manufacturer_code,
+            cgb,
+            new_licensee_code: ascii(&rom[NEW_LICENSEE_CODE..NEW_LICENSEE_CODE + 2]),
+            sgb: rom[SGB_FLAG] == 0x03,
+            cartridge_type,
+            rom_size_code,
+            ram_size_code,
+            rom_size,
+            ram_size,
+            destination: if rom[DESTINATION_CODE] == 0 { Destination::Japanese } else { Destination::Overseas },
+            old_licensee_code: rom[OLD_LICENSEE_CODE],
+            version: rom[VERSION],
+            header_checksum: rom[HEADER_CHECKSUM],
+            global_checksum: u16::from_be_bytes([rom[GLOBAL_CHECKSUM], rom[GLOBAL_CHECKSUM + 1]]),
+        };
+
+        let header_checksum = Self::compute_header_checksum(rom);
+        if header_checksum != header.header_checksum {
+            // The boot rom locks up on such a cartridge
+            return Err(EmulationError::HeaderChecksum {
+                expected: header.header_checksum,
+                computed: header_checksum,
+            });
+        }
+        let global_checksum = Self::compute_global_checksum(rom);
+        if global_checksum != header.global_checksum {
+            // Never checked by the hardware
+            info!(
+                "Global checksum mismatch: expected {:#06x}, computed {:#06x}",
+                header.global_checksum, global_checksum
+            );
+        }
+        if rom.len() < rom_size {
+            warn!("The rom is {} bytes but its header declares {} bytes", rom.len(), rom_size);
+        }
+        Ok(header)
+    }
+
+    /// Checksum of the bytes 0x0134-0x014C as computed by the boot rom.
+    pub fn compute_header_checksum(rom: &[u8]) -> u8 {
+        rom[TITLE..HEADER_CHECKSUM]
+            .iter()
+            .fold(0u8, |x, b| x.wrapping_sub(*b).wrapping_sub(1))
+    }
+
+    /// Sum of every byte of the rom except the global checksum itself.
+    pub fn compute_global_checksum(rom: &[u8]) -> u16 {
+        rom.iter()
+            .enumerate()
+            .filter(|(i, _)| *i != GLOBAL_CHECKSUM && *i != GLOBAL_CHECKSUM + 1)
+            .fold(0u16, |sum, (_, b)| sum.wrapping_add(*b as u16))
+    }
+
+    pub fn header_checksum_valid(&self, rom: &[u8]) -> bool {
+        Self::compute_header_checksum(rom) == self.header_checksum
+    }
+
+    pub fn global_checksum_valid(&self, rom: &[u8]) -> bool {
+        Self::compute_global_checksum(rom) == self.global_checksum
+    }
+
+    /// Publisher code, the new licensee code is used when the old one is 0x33.
+    pub fn licensee(&self) -> String {
+        if self.old_licensee_code == 0x33 {
+            self.new_licensee_code.clone()
+        } else {
+            format!("{:02X}", self.old_licensee_code)
+        }
+    }
+}
+
+/// A cartridge image with its parsed header, its external RAM and its memory bank controller.
+pub struct Cartridge {
+    pub header: CartridgeHeader,
+    pub rom: Vec<u8>,
+    pub ram: Vec<u8>,
+    mbc: Box<dyn Mbc>,
+    /// The battery backed state changed since the last `take_save_modified`
+    save_modified: bool,
+}
+
+impl Cartridge {
+    pub fn new(rom: &[u8]) -> Result<Self, EmulationError> {
+        let header = CartridgeHeader::parse(rom)?;
+        let cartridge_type = header.cartridge_type;
+        let (mbc, max_rom_banks): (Box<dyn Mbc>, usize) = match cartridge_type.mapper {
+            Mapper::RomOnly => (Box::new(RomOnly), 2),
+            Mapper::Mbc1 => (Box::new(Mbc1::new(rom)), 128),
+            Mapper::Mbc2 => (Box::new(Mbc2::new()), 16),
+            Mapper::Mbc3 => (Box::new(Mbc3::new(rom, header.ram_size, cartridge_type.timer)), 256),
+            Mapper::Mbc5 => (Box::new(Mbc5::new(cartridge_type.rumble)), 512),
+            Mapper::Mmm01 => (Box::new(Mmm01::new()), 512),
+            Mapper::Mbc6 => (Box::new(Mbc6::new()), 64),
+            Mapper::Mbc7 => (Box::new(Mbc7::new()), 128),
+            Mapper::PocketCamera => (Box::new(PocketCamera::new()), 64),
+            Mapper::Tama5 => (Box::new(Tama5::new()), 32),
+            Mapper::HuC3 => (Box::new(HuC3::new()), 128),
+            Mapper::HuC1 => (Box::new(HuC1::new()), 64),
+        };
+        if rom.len() > max_rom_banks * ROM_BANK_SIZE {
+            return Err(EmulationError::RomTooLarge { size: rom.len(), max: max_rom_banks * ROM_BANK_SIZE });
+        }
+        // The memories inside the controllers aren't declared by the header
+        let ram_size = match cartridge_type.mapper {
+            Mapper::Mbc2 => MBC2_RAM_SIZE,
+            Mapper::Mbc6 => MBC6_RAM_SIZE,
+            Mapper::Mbc7 => MBC7_EEPROM_SIZE,
+            Mapper::Tama5 => TAMA5_RAM_SIZE,
+            _ => header.ram_size,
+        };
+        let ram = vec![0; ram_size];
+        Ok(Cartridge { header, rom: rom.to_vec(), ram, mbc, save_modified: false })
+    }
+
+    /// Read in 0x0000-0x7FFF.
+    #[inline]
+    pub fn read_rom(&self, addr: u16) -> u8 {
+        self.mbc.read_rom(&self.rom, addr)
+    }
+
+    /// Write in 0x0000-0x7FFF, goes to the registers of the memory bank controller.
+    #[inline]
+    pub fn write_rom(&mut self, addr: u16, val: u8) {
+        // The flash of the MBC6 is programmed through the ROM area
+        self.save_modified |= self.mbc.write_rom(addr, val);
+    }
+
+    /// Read in 0xA000-0xBFFF.
+    #[inline]
+    pub fn read_ram(&self, addr: u16) -> u8 {
+        self.mbc.read_ram(&self.ram, addr)
+    }
+
+    /// Write in 0xA000-0xBFFF.
+    #[inline]
+    pub fn write_ram(&mut self, addr: u16, val: u8) {
+        self.save_modified |= self.mbc.write_ram(&mut self.ram, addr, val);
+    }
+
+    /// The RAM is kept by a battery when the console is off.
+    pub fn has_battery(&self) -> bool {
+        self.header.cartridge_type.battery
+    }
+
+    /// Whether the content of the RAM, the clock or the flash changed since the last call, to know
+    /// when to save it. The writes to the registers mapped over the RAM don't count.
+    pub fn take_save_modified(&mut self) -> bool {
+        std::mem::take(&mut self.save_modified)
+    }
+
+    /// Advance the clocked parts of the controller, `cycles` are at normal speed.
+    #[inline]
+    pub fn tick(&mut self, cycles: u16) {
+        self.mbc.tick(cycles)
+    }
+
+    /// State of the rumble motor, always off for the cartridges without one.
+    #[inline]
+    pub fn rumble(&self) -> bool {
+        self.mbc.rumble()
+    }
+
+    /// Tilt read by the accelerometer of the cartridge, if it has one.
+    pub fn set_tilt(&mut self, tilt: Tilt) {
+        self.mbc.set_tilt(tilt)
+    }
+
+    /// State of the infrared LED of the cartridge, always off for the cartridges without one.
+    pub fn ir_led(&self) -> bool {
+        self.mbc.ir_led()
+    }
+
+    /// Light received by the infrared sensor of the cartridge, if it has one.
+    pub fn set_ir_light(&mut self, light: bool) {
+        self.mbc.set_ir_light(light)
+    }
+
+    /// Source of the images of the camera cartridge.
+    pub fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.mbc.set_image_source(source)
+    }
+
+    /// Select what drives the real time clock of the cartridge, if it has one.
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.mbc.set_rtc_clock(clock)
+    }
+
+    /// The RAM followed by the state of the real time clock or flash, if the cartridge has one.
+    pub fn save_data(&self) -> Vec<u8> {
+        let mut data = self.ram.clone();
+        if let Some(extra) = self.mbc.save_extra() {
+            data.extend_from_slice(&extra);
+        }
+        data
+    }
+
+    /// Restore data produced by `save_data`, a missing trailer leaves the clock or flash untouched.
+    /// The size must be the size of the RAM, optionally followed by a complete trailer.
+    pub fn load_save_data(&mut self, data: &[u8]) -> Result<(), EmulationError> {
+        let ram_size = self.ram.len();
+        let extra_sizes = self.mbc.extra_sizes();
+        // A partial trailer would leave the clock without a timestamp or the flash half erased
+        let valid = data.len() == ram_size || extra_sizes.iter().any(|size| data.len() == ram_size + size);
+        if !valid {
+            let expected = ram_size + extra_sizes.first().copied().unwrap_or(0);
+            return Err(EmulationError::InvalidSaveSize { size: data.len(), expected });
+        }
+        self.ram.copy_from_slice(&data[..ram_size]);
+        let trailer = &data[ram_size..];
+        if !trailer.is_empty() {
+            self.mbc.load_extra(trailer);
+        }
+        Ok(())
+    }
+}
+
+/// Printable part of a header string, stops at the first NUL byte.
+fn ascii(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|b| **b != 0)
+        .map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '?' })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mbc::{HUC3_RTC_SAVE_SIZE, MBC6_FLASH_SIZE, RTC_SAVE_SIZE};
+
+    /// ROM of 32 KiB with the cartridge type and RAM size codes set in its header.
+    fn rom(cartridge_type: u8, ram_size_code: u8) -> Vec<u8> {
+        let mut rom = vec![0; 0x8000];
+        rom[CARTRIDGE_TYPE] = cartridge_type;
+        rom[RAM_SIZE] = ram_size_code;
+        rom[HEADER_CHECKSUM] = CartridgeHeader::compute_header_checksum(&rom);
+        rom
+    }
+
+    /// ROM only image with `bytes` written at `addr` in its header and valid checksums.
+    fn rom_with(addr: usize, bytes: &[u8]) -> Vec<u8> {
+        let mut rom = rom(0x00, 0x00);
+        rom[addr..addr + bytes.len()].copy_from_slice(bytes);
+        rom[HEADER_CHECKSUM] = CartridgeHeader::compute_header_checksum(&rom);
+        let global = CartridgeHeader::compute_global_checksum(&rom);
+        rom[GLOBAL_CHECKSUM..GLOBAL_CHECKSUM + 2].copy_from_slice(&global.to_be_bytes());
+        rom
+    }
+
+    #[test]
+    fn title_area() {
+        // (0x0134-0x0143, title, manufacturer code, CGB flag)
+        let cases: [(&[u8; 16], &str, Option<&str>, CgbSupport); 5] = [
+            (b"TETRIS\0\0\0\0\0\0\0\0\0\0", "TETRIS", None, CgbSupport::None),
+            // The old headers have a 16 characters title
+            (b"SUPER MARIOLAND2", "SUPER MARIOLAND2", None, CgbSupport::None),
+            (b"POKEMON_SLVAAXE\x80", "POKEMON_SLV", Some("AAXE"), CgbSupport::Enhanced),
+            (b"ZELDA\0\0\0\0\0\0\0\0\0\0\x80", "ZELDA", None, CgbSupport::Enhanced),
+            // Not a manufacturer code, the title is 15 characters
+            (b"TITLE OF 15 CHR\xC0", "TITLE OF 15 CHR", None, CgbSupport::Only),
+        ];
+        for (area, title, manufacturer_code, cgb) in cases {
+            let header = CartridgeHeader::parse(&rom_with(TITLE, area)).unwrap();
+            assert_eq!(header.title, title);
+            assert_eq!(header.manufacturer_code.as_deref(), manufacturer_code, "{}", title);
+            assert_eq!(header.cgb, cgb, "{}", title);
+        }
+    }
+
+    #[test]
+    fn checksums() {
+        let rom = rom_with(TITLE, b"TETRIS");
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert!(header.header_checksum_valid(&rom));
+        assert!(header.global_checksum_valid(&rom));
+
+        // The global checksum is never checked by the hardware, only logged
+        let mut bad_global = rom.clone();
+        bad_global[0x1000] = 0x42;
+        let header = CartridgeHeader::parse(&bad_global).unwrap();
+        assert!(!header.global_checksum_valid(&bad_global));
+
+        let mut bad_header = rom.clone();
+        bad_header[VERSION] = 0x01;
+        let computed = CartridgeHeader::compute_header_checksum(&bad_header);
+        assert_eq!(
+            CartridgeHeader::parse(&bad_header),
+            Err(EmulationError::HeaderChecksum { expected: rom[HEADER_CHECKSUM], computed })
+        );
+    }
+
+    #[test]
+    fn header_values() {
+        // (address, value, ROM size or error)
+        let cases = [
+            (ROM_SIZE, 0x00, Ok(0x8000)),
+            (ROM_SIZE, 0x08, Ok(0x80_0000)),
+            (ROM_SIZE, 0x52, Ok(72 * ROM_BANK_SIZE)),
+            (ROM_SIZE, 0x09, Err(EmulationError::InvalidHeader { field: "rom size", value: 0x09 })),
+            (RAM_SIZE, 0x06, Err(EmulationError::InvalidHeader { field: "ram size", value: 0x06 })),
+            (CARTRIDGE_TYPE, 0x04, Err(EmulationError::UnsupportedCartridge(0x04))),
+        ];
+        for (addr, value, expected) in cases {
+            let header = CartridgeHeader::parse(&rom_with(addr, &[value]));
+            assert_eq!(header.map(|h| h.rom_size), expected, "{:#04x} at {:#06x}", value, addr);
+        }
+    }
+
+    #[test]
+    fn rom_without_header() {
+        let rom = vec![0; HEADER_END - 1];
+        assert_eq!(CartridgeHeader::parse(&rom), Err(EmulationError::RomTooSmall(HEADER_END - 1)));
+        assert!(Cartridge::new(&rom).is_err());
+        assert!(CartridgeHeader::parse(&rom_with(TITLE, b"")[..HEADER_END]).is_ok());
+    }
+
+    #[test]
+    fn save_sizes() {
+        // (cartridge type, RAM size code, save size, accepted)
+        let cases = [
+            // MBC3 with a clock, 32 KiB of RAM and the 48 or 44 bytes trailer
+            (0x10, 0x03, 0x8000, true),
+            (0x10, 0x03, 0x8000 + 48, true),
+            (0x10, 0x03, 0x8000 + 44, true),
+            (0x10, 0x03, 0x8000 + 20, false),
+            (0x10, 0x03, 0x8000 + 1, false),
+            (0x10, 0x03, 0x8000 + 49, false),
+            (0x10, 0x03, 0x7FFF, false),
+            // MBC3 without a clock has no trailer
+            (0x13, 0x03, 0x8000, true),
+            (0x13, 0x03, 0x8000 + 48, false),
+            // HuC3
+            (0xFE, 0x03, 0x8000 + HUC3_RTC_SAVE_SIZE, true),
+            (0xFE, 0x03, 0x8000 + 8, false),
+            // MBC6, the flash is saved whole
+            (0x20, 0x00, MBC6_RAM_SIZE + MBC6_FLASH_SIZE, true),
+            (0x20, 0x00, MBC6_RAM_SIZE + 0x1000, false),
+        ];
+        for (cartridge_type, ram_size_code, size, accepted) in cases {
+            let mut cartridge = Cartridge::new(&rom(cartridge_type, ram_size_code)).unwrap();
+            let result = cartridge.load_save_data(&vec![0; size]);
+            assert_eq!(result.is_ok(), accepted, "type {:#04x}, save of {} bytes", cartridge_type, size);
+        }
+    }
+
+    #[test]
+    fn truncated_rtc_trailer_is_rejected() {
+        let mut cartridge = Cartridge::new(&rom(0x10, 0x03)).unwrap();
+        let mut save = cartridge.save_data();
+        save.truncate(0x8000 + 20);
+        assert_eq!(
+            cartridge.load_save_data(&save),
+            Err(EmulationError::InvalidSaveSize { size: 0x8000 + 20, expected: 0x8000 + RTC_SAVE_SIZE })
+        );
+    }
+}
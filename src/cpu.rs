@@ -1,5 +1,8 @@
+use crate::error::EmulationError;
+use crate::interrupts::Interrupt;
 use crate::mmu::MMU;
-use crate::op_codes::OP_CODES;
+use crate::op_codes::{Exec, CB_OP_CODES, OP_CODES};
+use crate::timer::DIV_ADDR;
 use log::*;
 use bitflags::bitflags;
 
@@ -21,7 +24,79 @@ impl Default for Flags {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+/// 16 bits registers, made of two 8 bits registers except SP.
+/// The first register of the pair is the high byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reg16 {
+    AF,
+    BC,
+    DE,
+    HL,
+    SP,
+}
+
+/// Condition codes of the conditional jumps, calls and returns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    /// Unconditional version of the instruction
+    Always,
+    /// Zero flag reset
+    NZ,
+    /// Zero flag set
+    Z,
+    /// Carry flag reset
+    NC,
+    /// Carry flag set
+    C,
+}
+
+impl Condition {
+    /// Evaluate the condition against the individual flags.
+    #[inline]
+    pub fn check(self, flags: Flags) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::NZ => !flags.contains(Flags::ZERO),
+            Condition::Z => flags.contains(Flags::ZERO),
+            Condition::NC => !flags.contains(Flags::CARRY),
+            Condition::C => flags.contains(Flags::CARRY),
+        }
+    }
+}
+
+/// What the cpu does when it fetches an illegal opcode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IllegalOpcodeMode {
+    /// Stop and report an `EmulationError::IllegalOpcode`
+    #[default]
+    Error,
+    /// Freeze like the real hardware, only a reset gets the cpu out of it
+    Lockup,
+}
+
+/// Number of cycles (at normal speed) between two frames.
+pub const CYCLES_PER_FRAME: u64 = 70224;
+
+/// What the cpu did during a `Cpu::step`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Executed {
+    /// An instruction at `addr`, for the CB prefixed instructions `opcode` is the byte after the
+    /// prefix.
+    Instruction { addr: u16, opcode: u8, prefixed: bool, name: &'static str },
+    /// The dispatch of an interrupt, `vector` is 0x0000 when the dispatch was cancelled.
+    Interrupt { vector: u16 },
+    /// The cpu is halted, stopped or locked.
+    Idle,
+}
+
+/// Result of a `Cpu::step`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub executed: Executed,
+    pub cycles: u16,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Cpu {
     pub a: Register,
 
@@ -39,29 +114,23 @@ pub struct Cpu {
     pub sp: Register<u16>,
 
     pub int_clk: u16,
-    pub clock: u16,
-
-    pub boot_off : bool
-}
-
-impl Default for Cpu {
-    fn default() -> Self {
-        Cpu {
-            a: 0,
-            b: 0,
-            c: 0,
-            d: 0,
-            e: 0,
-            h: 0,
-            l: 0,
-            f: Flags::default(),
-            pc: 0,
-            sp: 0,
-            int_clk: 0,
-            clock: 0,
-            boot_off: false
-        }
-    }
+    pub clock: u64,
+
+    /// Interrupt master enable
+    pub ime: bool,
+    /// Set by EI, the IME is only enabled after the instruction following EI
+    pub ei_delay: bool,
+
+    /// Low power mode entered by HALT, left when an interrupt is pending
+    pub halted: bool,
+    /// HALT executed with IME=0 and an interrupt pending, the next opcode byte is read twice
+    pub halt_bug: bool,
+    /// Very low power mode entered by STOP, left when a joypad input is received
+    pub stopped: bool,
+
+    pub on_illegal_opcode: IllegalOpcodeMode,
+    /// An illegal opcode froze the cpu, see `IllegalOpcodeMode::Lockup`
+    pub locked: bool,
 }
 
 impl Cpu {
@@ -75,26 +144,73 @@ impl Cpu {
 
     }
 
+    #[inline]
+    pub fn r16(&self, reg: Reg16) -> u16 {
+        match reg {
+            Reg16::AF => self.r_af(),
+            Reg16::BC => self.r_bc(),
+            Reg16::DE => self.r_de(),
+            Reg16::HL => self.r_hl(),
+            Reg16::SP => self.sp,
+        }
+    }
+
+    #[inline]
+    pub fn w16(&mut self, reg: Reg16, val: u16) {
+        match reg {
+            Reg16::AF => self.w_af(val),
+            Reg16::BC => self.w_bc(val),
+            Reg16::DE => self.w_de(val),
+            Reg16::HL => self.w_hl(val),
+            Reg16::SP => self.sp = val,
+        }
+    }
+
+    #[inline]
+    pub fn r_af(&self) -> u16 {
+        u16::from_be_bytes([self.a, self.f.bits()])
+    }
+
+    /// The lower nibble of F doesn't exist and always reads as 0.
+    #[inline]
+    pub fn w_af(&mut self, val: u16) {
+        let [a, f] = val.to_be_bytes();
+        self.a = a;
+        self.f = Flags::from_bits_truncate(f);
+    }
+
+    #[inline]
+    pub fn r_bc(&self) -> u16 {
+        u16::from_be_bytes([self.b, self.c])
+    }
+
+    #[inline]
+    pub fn w_bc(&mut self, val: u16) {
+        let [b, c] = val.to_be_bytes();
+        self.b = b;
+        self.c = c;
+    }
+
     #[inline]
     pub fn r_hl(&self) -> u16 {
-        u16::from_le_bytes([self.h, self.l])
+        u16::from_be_bytes([self.h, self.l])
     }
 
     #[inline]
     pub fn w_hl(&mut self, val: u16) {
-        let [x1, x2] = val.to_le_bytes();
-        self.h = x1;
-        self.l = x2;
+        let [h, l] = val.to_be_bytes();
+        self.h = h;
+        self.l = l;
     }
 
     #[inline]
-    pub fn r_de(&mut self) -> u16 {
-        u16::from_le_bytes([self.d, self.e])
+    pub fn r_de(&self) -> u16 {
+        u16::from_be_bytes([self.d, self.e])
     }
 
     #[inline]
     pub fn w_de(&mut self, val: u16) {
-        let [d, e] = val.to_le_bytes();
+        let [d, e] = val.to_be_bytes();
         self.d = d;
         self.e = e;
     }
@@ -103,44 +219,215 @@ impl Cpu {
         *self = Self::default();
     }
 
-    pub fn run(&mut self, memory: &mut MMU) {
+    /// Run until an error occurs.
+    pub fn run(&mut self, memory: &mut MMU) -> Result<(), EmulationError> {
         loop {
-            let op = memory.rb(self.pc);
-            debug!("Dump: sp:{:#x} pc:{:#x}", self.sp, self.pc);
-            let op_code = &OP_CODES[op as usize];
-            self.pc += 1; // increment the program counter to the next instruction
-            let operands = match op_code.operand_size {
-                1 => memory.rb(self.pc) as u16,
-                2 => memory.rw(self.pc),
-                _ => 0
-            };
-            self.pc += op_code.operand_size as u16;
-            self.int_clk = 0;
-            debug!("OpCode:{:#x}, name {}, Operands: {:#x}", op, op_code.name, operands);
-            (OP_CODES[op as usize].f)(self, memory, operands); // Running the opcode behaviour
-            if self.int_clk == 0 {
-                self.int_clk = op_code.time
+            self.step(memory)?;
+        }
+    }
+
+    /// Run at least `cycles` cycles, the last instruction may overshoot.
+    /// Returns the number of cycles actually run.
+    pub fn run_cycles(&mut self, memory: &mut MMU, cycles: u64) -> Result<u64, EmulationError> {
+        let mut elapsed = 0;
+        while elapsed < cycles {
+            elapsed += self.step(memory)?.cycles as u64;
+        }
+        Ok(elapsed)
+    }
+
+    /// Run for the duration of one frame, twice as many cycles in CGB double speed.
+    pub fn run_frame(&mut self, memory: &mut MMU) -> Result<u64, EmulationError> {
+        let cycles = CYCLES_PER_FRAME << memory.double_speed as u8;
+        self.run_cycles(memory, cycles)
+    }
+
+    /// Run until the PPU completes a frame and enters VBlank. When the lcd is off no VBlank
+    /// happens so it gives up after the duration of a frame.
+    /// Returns the number of cycles run.
+    pub fn run_until_vblank(&mut self, memory: &mut MMU) -> Result<u64, EmulationError> {
+        let max_cycles = CYCLES_PER_FRAME << memory.double_speed as u8;
+        let frames = memory.ppu.frames;
+        let mut elapsed = 0;
+        while elapsed < max_cycles && memory.ppu.frames == frames {
+            elapsed += self.step(memory)?.cycles as u64;
+        }
+        Ok(elapsed)
+    }
+
+    /// Service a pending interrupt or execute the next instruction.
+    pub fn step(&mut self, memory: &mut MMU) -> Result<Step, EmulationError> {
+        let step = self.step_inner(memory)?;
+        self.clock += step.cycles as u64;
+        memory.tick(step.cycles);
+        Ok(step)
+    }
+
+    fn step_inner(&mut self, memory: &mut MMU) -> Result<Step, EmulationError> {
+        const IDLE: Step = Step { executed: Executed::Idle, cycles: 4 };
+        if self.locked {
+            // Nothing happens anymore, not even interrupts
+            return Ok(IDLE);
+        }
+        if self.stopped {
+            // There is no joypad line to watch, a joypad interrupt request is used as wake up
+            if memory.interrupt_flag & Interrupt::JOYPAD.bits() == 0 {
+                return Ok(IDLE);
+            }
+            self.stopped = false;
+        }
+        let mut wake_up = 0;
+        if self.halted {
+            // HALT is left as soon as an interrupt is pending, even if the IME is not set
+            if memory.pending_interrupts() == 0 {
+                return Ok(IDLE);
+            }
+            self.halted = false;
+            wake_up = 4;
+        }
+        if let Some(mut step) = self.handle_interrupts(memory) {
+            step.cycles += wake_up;
+            return Ok(step);
+        }
+        if self.ei_delay {
+            // EI takes effect after the instruction following it
+            self.ei_delay = false;
+            self.ime = true;
+        }
+        self.execute(memory)
+    }
+
+    /// Fetch, decode and execute one instruction.
+    fn execute(&mut self, memory: &mut MMU) -> Result<Step, EmulationError> {
+        let op_addr = self.pc;
+        let mut op = memory.rb(self.pc);
+        debug!("Dump: sp:{:#x} pc:{:#x}", self.sp, self.pc);
+        if self.halt_bug {
+            // The pc fails to increment so the byte after HALT is read twice
+            self.halt_bug = false;
+        } else {
+            self.pc = self.pc.wrapping_add(1); // increment the program counter to the next instruction
+        }
+        let prefixed = op == 0xCB;
+        let op_code = if prefixed {
+            // The CB prefix selects the second page, the next byte is the real opcode
+            op = memory.rb(self.pc);
+            self.pc = self.pc.wrapping_add(1);
+            &CB_OP_CODES[op as usize]
+        } else {
+            &OP_CODES[op as usize]
+        };
+        let operand_addr = self.pc;
+        self.pc = self.pc.wrapping_add(op_code.operand_size() as u16);
+        self.int_clk = 0;
+        debug!("OpCode:{:#x}, name {}", op, op_code.name);
+        // Running the opcode behaviour with its immediate operand
+        match op_code.f {
+            Exec::None(f) => f(self, memory),
+            Exec::U8(f) => f(self, memory, memory.rb(operand_addr)),
+            Exec::I8(f) => f(self, memory, memory.rb(operand_addr) as i8),
+            Exec::U16(f) => f(self, memory, memory.rw(operand_addr)),
+            Exec::Illegal => {
+                self.pc = op_addr;
+                match self.on_illegal_opcode {
+                    IllegalOpcodeMode::Error => {
+                        return Err(EmulationError::IllegalOpcode { opcode: op, addr: op_addr });
+                    }
+                    IllegalOpcodeMode::Lockup => {
+                        warn!("Illegal opcode {:#04x} at {:#06x}, the cpu is locked", op, op_addr);
+                        self.locked = true;
+                    }
+                }
             }
-            self.clock += self.int_clk;
         }
+        if self.int_clk == 0 {
+            self.int_clk = op_code.time
+        }
+        Ok(Step {
+            executed: Executed::Instruction { addr: op_addr, opcode: op, prefixed, name: op_code.name },
+            cycles: self.int_clk,
+        })
     }
 
-    pub fn pop_stack<const BYTES:usize>(&mut self, mem: &mut MMU) -> [u8; BYTES]  {
-        let pc = self.pc;
-        self.pc+=BYTES as u16;
-        mem.read::<BYTES>(pc)
+    /// Dispatch the highest priority pending interrupt if the IME is set.
+    /// The dispatch takes 20 cycles: the IME is reset, the pc is pushed and the cpu jumps to the
+    /// interrupt vector. Returns None when no interrupt was serviced.
+    pub fn handle_interrupts(&mut self, mem: &mut MMU) -> Option<Step> {
+        if !self.ime || mem.pending_interrupts() == 0 {
+            return None;
+        }
+        self.ime = false;
+        self.ei_delay = false;
+        let [low, high] = self.pc.to_le_bytes();
+        self.sp = self.sp.wrapping_sub(1);
+        mem.wb(self.sp, high);
+        // The interrupt to service is chosen after the high byte is pushed, if that push
+        // overwrote IE (sp = 0x0000) the dispatch can be cancelled and jumps to 0x0000
+        let interrupt = Interrupt::highest_priority(mem.pending_interrupts());
+        self.sp = self.sp.wrapping_sub(1);
+        mem.wb(self.sp, low);
+        let vector = match interrupt {
+            Some(interrupt) => {
+                debug!("Servicing interrupt {:?}", interrupt);
+                mem.interrupt_flag &= !interrupt.bits();
+                interrupt.vector()
+            }
+            None => 0x0000,
+        };
+        self.jmp(vector);
+        Some(Step { executed: Executed::Interrupt { vector }, cycles: 20 })
     }
 
-    /// Push a u16 value onto the stack, the stack pointer is at the end of the momory so the pointer
-    /// is mooving backwards
-    pub fn push_stack<const BYTES: usize>(&mut self, mem: &mut MMU,val : [u8;BYTES]) {
-        mem.write(self.sp,val);
-        self.sp -= BYTES as u16;
+    /// 0x76
+    /// Halt the cpu until an interrupt is pending. When the IME is not set and an interrupt is
+    /// already pending the cpu doesn't halt and the halt bug is triggered instead.
+    pub fn halt(&mut self, mem: &MMU) {
+        if !self.ime && mem.pending_interrupts() != 0 {
+            self.halt_bug = true;
+        } else {
+            self.halted = true;
+        }
     }
 
-    /// Push the actual address into the stack the increment the stack ptr;
+    /// 0x10
+    /// Stop the cpu and the lcd until a button is pressed. On CGB, when a speed switch was
+    /// prepared through KEY1, STOP switches the cpu speed instead.
+    pub fn stop(&mut self, mem: &mut MMU) {
+        if mem.cgb_mode && mem.prepare_speed_switch {
+            mem.prepare_speed_switch = false;
+            mem.double_speed = !mem.double_speed;
+            debug!("Speed switch, double speed: {}", mem.double_speed);
+            // The cpu is paused while the clock stabilises
+            self.int_clk = 8200;
+        } else {
+            self.stopped = true;
+        }
+        // Same as a write to DIV, the reset can increment TIMA
+        mem.wb(DIV_ADDR, 0);
+    }
+
+    /// Pop a u16 from the stack, the low byte is read first then the stack pointer moves up.
+    pub fn pop_stack(&mut self, mem: &mut MMU) -> u16 {
+        let low = mem.rb(self.sp);
+        self.sp = self.sp.wrapping_add(1);
+        let high = mem.rb(self.sp);
+        self.sp = self.sp.wrapping_add(1);
+        u16::from_le_bytes([low, high])
+    }
+
+    /// Push a u16 value onto the stack, the stack grows downwards so the stack pointer is
+    /// decremented before each write, the high byte is written first.
+    pub fn push_stack(&mut self, mem: &mut MMU, val: u16) {
+        let [low, high] = val.to_le_bytes();
+        self.sp = self.sp.wrapping_sub(1);
+        mem.wb(self.sp, high);
+        self.sp = self.sp.wrapping_sub(1);
+        mem.wb(self.sp, low);
+    }
+
+    /// Push the actual address into the stack.
     pub fn push_pc_stack(&mut self, mem: &mut MMU) {
-        self.push_stack(mem,self.pc.to_le_bytes());
+        self.push_stack(mem, self.pc);
     }
 
     /// jump to the new address
@@ -148,90 +435,80 @@ impl Cpu {
         self.pc = addr;
     }
 
-    /// Description:
-    ///   Add n to HL.
-    ///  Use with:
-    ///   n = BC,DE,HL,SP
-    ///  Flags affected:
-    ///   Z - Not affected.
-    ///   N - Reset.
-    ///   H - Set if carry from bit 11.
-    ///   C - Set if carry from bit 15.
-    pub fn add_u16(&mut self, to: &mut u16, from: u16) {
-        let result = from as u32 + *to as u32;
-        self.set_flag_or_reset(Flags::CARRY, result & 0xffff0000 != 0 );
-        *to = result as u16 & 0xffff;
-        self.set_flag_or_reset(Flags::HALFCARRY, ((*to & 0x0f) + (from & 0x0f)) > 0x0f);
-        self.f.remove(Flags::NEGATIVE)
+    /// INC rr, no flags are affected.
+    pub fn inc16(&mut self, reg: Reg16) {
+        self.w16(reg, self.r16(reg).wrapping_add(1));
     }
 
-    pub fn dec(register: &mut u8) {
-        // TODO
-        unimplemented!()
+    /// DEC rr, no flags are affected.
+    pub fn dec16(&mut self, reg: Reg16) {
+        self.w16(reg, self.r16(reg).wrapping_sub(1));
     }
 
-
-    pub fn sbc_a(&mut self, operand: u8) {
-        let result = self.a as i32 - operand as i32;
-        self.f |= Flags::NEGATIVE;
-        self.set_flag_or_reset(Flags::ZERO, result == 0);
-        self.set_flag_or_reset(Flags::CARRY, operand > self.a);
-        self.set_flag_or_reset(Flags::HALFCARRY, (operand & 0x0f) > (self.a & 0x0f));
-        self.a.wrapping_sub(operand);
+    /// PUSH rr
+    pub fn push16(&mut self, mem: &mut MMU, reg: Reg16) {
+        self.push_stack(mem, self.r16(reg));
     }
 
-    /// 0x19
-    pub fn add_hl_de(&mut self) {
-        let mut hl = self.r_hl();
-        let de = self.r_de();
-
-        self.add_u16(&mut hl, de);
-        self.w_hl(hl);
+    /// POP rr
+    pub fn pop16(&mut self, mem: &mut MMU, reg: Reg16) {
+        let val = self.pop_stack(mem);
+        self.w16(reg, val);
     }
 
-    /// 0x20
-    /// All the jr are handled bu this function, id the control and the cc are the same
-    /// then jump to the location bu the addr in jmp.
-    pub fn jr(&mut self, ctrl: Flags, offset_jmp: u8) {
-        if ctrl == self.f {
-            self.jmp(self.pc + offset_jmp as u16);
+    /// JR cc,e8
+    /// Relative jump to pc + the signed offset when the condition holds.
+    /// Takes 12 cycles when the jump is done, 8 otherwise.
+    pub fn jr(&mut self, cond: Condition, offset: i8) {
+        if cond.check(self.f) {
+            self.jmp(self.pc.wrapping_add(offset as u16));
+            self.int_clk = 12;
         }
     }
 
-    /// 0x31 opcode
-    /// Load 16 bites to sp register
-    pub fn ld_sp_nn(&mut self, operands: u16) {
-        debug!("Loaded {} into sp", operands);
-        self.sp = operands;
+    /// JP cc,nn
+    /// Absolute jump when the condition holds.
+    /// Takes 16 cycles when the jump is done, 12 otherwise.
+    pub fn jp(&mut self, cond: Condition, addr: u16) {
+        if cond.check(self.f) {
+            self.jmp(addr);
+            self.int_clk = 16;
+        }
     }
 
-    /// 0xAF
-    /// XOR N
-    /// Logical exclusive OR n with register A, result in A.
-    pub fn xor_n(&mut self, operands: u16) {
-        self.f &= Flags::empty();
-        self.a ^= operands as u8;
-        if self.a == 0 {
-            self.f = Flags::ZERO;
+    /// CALL cc,nn
+    /// Push the address of the next instruction and jump to the address when the condition holds.
+    /// Takes 24 cycles when the call is done, 12 otherwise.
+    pub fn call(&mut self, cond: Condition, addr: u16, mem: &mut MMU) {
+        if cond.check(self.f) {
+            self.push_pc_stack(mem);
+            self.jmp(addr);
+            self.int_clk = 24;
         }
     }
-    /// 0xFE
-    /// Compare A with n. This is basically an A - n
-    ///   subtraction instruction but the results are thrown
-    ///   away
-    #[inline]
-    pub fn cp_n(&mut self, operand: u16) {
-        let operand = operand as u8;
-        self.f &= Flags::empty();
 
-        if self.a == operand {
-            self.f |= Flags::ZERO;
-        } else if self.a < operand {
-            self.f |= Flags::CARRY;
-        } else if (operand & 0x0f) > self.a & 0x0f {
-            self.f |= Flags::HALFCARRY;
+    /// RET cc
+    /// Pop the return address when the condition holds.
+    /// Takes 20 cycles when the cpu returns, 8 otherwise (one more cycle than RET to check the
+    /// condition).
+    pub fn ret_cc(&mut self, cond: Condition, mem: &mut MMU) {
+        if cond.check(self.f) {
+            self.ret(mem);
+            self.int_clk = 20;
         }
-        self.f |= Flags::NEGATIVE;
+    }
+
+    /// Pop the return address from the stack then jump to it.
+    pub fn ret(&mut self, mem: &mut MMU) {
+        let addr = self.pop_stack(mem);
+        self.jmp(addr);
+    }
+
+    /// 0xD9
+    /// Return from an interrupt handler, the IME is enabled without the EI delay.
+    pub fn reti(&mut self, mem: &mut MMU) {
+        self.ret(mem);
+        self.ime = true;
     }
 
     /// Generic reset
@@ -241,3 +518,152 @@ impl Cpu {
         self.jmp(n);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmu::KEY1_ADDR;
+    use crate::timer::{TAC_ADDR, TIMA_ADDR};
+
+    /// Address the test programs are loaded at, in the WRAM.
+    const PROGRAM: u16 = 0xC000;
+
+    /// Cpu about to run `program` with the VBlank interrupt enabled and requested.
+    fn setup(program: &[u8]) -> (Cpu, MMU) {
+        let mut mmu = MMU::default();
+        mmu.wram[..program.len()].copy_from_slice(program);
+        mmu.interrupt_enable = Interrupt::VBLANK.bits();
+        mmu.request_interrupt(Interrupt::VBLANK);
+        let cpu = Cpu { pc: PROGRAM, sp: 0xDFF0, ..Cpu::default() };
+        (cpu, mmu)
+    }
+
+    /// Run one step per expected (pc, IME) and check them after each step.
+    fn check_steps(cpu: &mut Cpu, mmu: &mut MMU, steps: &[(u16, bool)]) {
+        for (i, &(pc, ime)) in steps.iter().enumerate() {
+            let step = cpu.step(mmu).unwrap();
+            assert_eq!((cpu.pc, cpu.ime), (pc, ime), "step {}: {:?}", i, step.executed);
+        }
+    }
+
+    #[test]
+    fn ei_delay() {
+        // EI, NOP, NOP
+        let (mut cpu, mut mmu) = setup(&[0xFB, 0x00, 0x00]);
+        check_steps(
+            &mut cpu,
+            &mut mmu,
+            &[
+                // The instruction after EI runs before the interrupt
+                (0xC001, false),
+                (0xC002, true),
+                (0x0040, false),
+            ],
+        );
+        assert_eq!(mmu.rw(cpu.sp), 0xC002);
+        assert_eq!(mmu.interrupt_flag & Interrupt::VBLANK.bits(), 0);
+    }
+
+    #[test]
+    fn di_right_after_ei() {
+        // EI, DI, NOP
+        let (mut cpu, mut mmu) = setup(&[0xFB, 0xF3, 0x00]);
+        check_steps(&mut cpu, &mut mmu, &[(0xC001, false), (0xC002, false), (0xC003, false)]);
+    }
+
+    #[test]
+    fn reti_enables_at_once() {
+        // RETI to 0xC010, NOP there
+        let (mut cpu, mut mmu) = setup(&[0xD9]);
+        cpu.sp = 0xDFEE;
+        mmu.ww(0xDFEE, 0xC010);
+        check_steps(&mut cpu, &mut mmu, &[(0xC010, true), (0x0040, false)]);
+        assert_eq!(mmu.rw(cpu.sp), 0xC010);
+    }
+
+    #[test]
+    fn push_into_ie_during_dispatch() {
+        // (pc, IE and IF, vector jumped to)
+        let cases = [
+            // The high byte of the pc lands in IE and disables the VBlank interrupt
+            (0xC000, Interrupt::VBLANK, 0x0000),
+            // 0xC1 keeps it enabled
+            (0xC100, Interrupt::VBLANK, 0x0040),
+            // 0x04 only leaves the timer interrupt, it is serviced instead of VBlank
+            (0x0400, Interrupt::VBLANK | Interrupt::TIMER, 0x0050),
+        ];
+        for (pc, interrupts, vector) in cases {
+            let mut mmu = MMU::default();
+            mmu.interrupt_enable = interrupts.bits();
+            mmu.request_interrupt(interrupts);
+            // The high byte is pushed to 0xFFFF
+            let mut cpu = Cpu { pc, sp: 0x0000, ime: true, ..Cpu::default() };
+            let step = cpu.step(&mut mmu).unwrap();
+            assert_eq!(step.executed, Executed::Interrupt { vector }, "pc {:#06x}", pc);
+            assert_eq!((cpu.pc, cpu.ime), (vector, false), "pc {:#06x}", pc);
+            assert_eq!(mmu.interrupt_enable, (pc >> 8) as u8, "pc {:#06x}", pc);
+            // A cancelled dispatch doesn't acknowledge anything
+            let acknowledged = interrupts.bits() & !mmu.interrupt_flag;
+            assert_eq!(acknowledged.count_ones(), (vector != 0) as u32, "pc {:#06x}", pc);
+        }
+    }
+
+    #[test]
+    fn halt_bug() {
+        // HALT, INC A, NOP with IME=0 and the VBlank interrupt pending
+        let (mut cpu, mut mmu) = setup(&[0x76, 0x3C, 0x00]);
+        cpu.step(&mut mmu).unwrap();
+        assert!(!cpu.halted);
+        // The byte after HALT is executed twice
+        check_steps(&mut cpu, &mut mmu, &[(0xC001, false), (0xC002, false), (0xC003, false)]);
+        assert_eq!(cpu.a, 2);
+    }
+
+    #[test]
+    fn halt_without_pending_interrupt() {
+        // HALT, INC A with IME=0
+        let (mut cpu, mut mmu) = setup(&[0x76, 0x3C]);
+        mmu.interrupt_flag = 0;
+        cpu.step(&mut mmu).unwrap();
+        assert!(cpu.halted);
+        assert_eq!(cpu.step(&mut mmu).unwrap().executed, Executed::Idle);
+        // Woken up by the request but not serviced
+        mmu.request_interrupt(Interrupt::VBLANK);
+        check_steps(&mut cpu, &mut mmu, &[(0xC002, false)]);
+        assert_eq!(cpu.a, 1);
+    }
+
+    #[test]
+    fn speed_switch() {
+        // LD A,1; LDH (KEY1),A; STOP 0
+        let program = [0x3E, 0x01, 0xE0, 0x4D, 0x10, 0x00];
+        // (CGB, KEY1 after STOP, stopped, cycles of STOP)
+        let cases = [(true, 0xFE, false, 8200), (false, 0xFF, true, 4)];
+        for (cgb, key1, stopped, cycles) in cases {
+            let (mut cpu, mut mmu) = setup(&program);
+            mmu.cgb_mode = cgb;
+            cpu.step(&mut mmu).unwrap();
+            cpu.step(&mut mmu).unwrap();
+            assert_eq!(mmu.prepare_speed_switch, cgb, "CGB {}", cgb);
+            let step = cpu.step(&mut mmu).unwrap();
+            assert_eq!(step.cycles, cycles, "CGB {}", cgb);
+            assert_eq!(mmu.rb(KEY1_ADDR), key1, "CGB {}", cgb);
+            assert_eq!(mmu.double_speed, cgb, "CGB {}", cgb);
+            assert_eq!(cpu.stopped, stopped, "CGB {}", cgb);
+        }
+    }
+
+    #[test]
+    fn stop_resets_div_like_a_write() {
+        // STOP 0 with the timer on the counter bit 3, which is set
+        let (mut cpu, mut mmu) = setup(&[0x10, 0x00]);
+        mmu.wb(TAC_ADDR, 0x05);
+        mmu.wb(TIMA_ADDR, 0x10);
+        mmu.timer.set_counter(0x1238);
+        cpu.step(&mut mmu).unwrap();
+        assert!(cpu.stopped);
+        assert_eq!(mmu.rb(DIV_ADDR), 0x00);
+        // The falling edge of the reset
+        assert_eq!(mmu.rb(TIMA_ADDR), 0x11);
+    }
+}
@@ -0,0 +1,114 @@
+//! OAM DMA register (0xFF46), copies 0xXX00-0xXX9F to the OAM at one byte per M-cycle.
+
+pub const DMA_ADDR: u16 = 0xFF46;
+
+/// Bytes copied, the size of the OAM.
+const LENGTH: u8 = 0xA0;
+/// The first byte is copied one M-cycle after the transfer starts.
+const START_DELAY: u16 = 4;
+
+/// A transfer owns the bus it reads from, the cpu keeps access to HRAM and the IO registers.
+#[derive(Clone, Debug, Default)]
+pub struct OamDma {
+    /// High byte of the source address, the value last written to 0xFF46
+    pub source: u8,
+    /// Cycles since the transfer started, None when idle
+    elapsed: Option<u16>,
+    /// Bytes already copied
+    copied: u8,
+    /// 0xFF46 was written during the instruction the next tick is for
+    requested: bool,
+    /// A transfer is copying, stays set during the start delay of a restarted transfer
+    owns_bus: bool,
+}
+
+impl OamDma {
+    /// Request a transfer from `source`, a transfer in progress goes on until the new one starts.
+    pub fn start(&mut self, source: u8) {
+        self.source = source;
+        self.requested = true;
+    }
+
+    /// Whether a transfer is copying, the OAM and the source bus are unusable by the cpu.
+    pub fn active(&self) -> bool {
+        self.owns_bus
+    }
+
+    /// Advance by `cycles`, returns the indexes of the bytes to copy now.
+    pub fn tick(&mut self, cycles: u16) -> std::ops::Range<u8> {
+        // The register is written at the end of the instruction, the cycles before don't count
+        if std::mem::take(&mut self.requested) {
+            self.elapsed = Some(0);
+            self.copied = 0;
+            return 0..0;
+        }
+        let Some(elapsed) = self.elapsed.as_mut() else {
+            return 0..0;
+        };
+        *elapsed += cycles;
+        // A restarted transfer keeps the bus during its start delay
+        self.owns_bus |= *elapsed >= START_DELAY;
+        let start = self.copied;
+        self.copied = (elapsed.saturating_sub(START_DELAY) / 4).min(LENGTH as u16) as u8;
+        if self.copied == LENGTH {
+            self.elapsed = None;
+            self.owns_bus = false;
+        }
+        start..self.copied
+    }
+
+    /// Address of the byte `index` of the source, the sources from 0xE000 read the WRAM like the
+    /// echo RAM does.
+    pub fn source_addr(&self, index: u8) -> u16 {
+        let base = (self.source as u16) << 8;
+        let base = if base >= 0xE000 { base - 0x2000 } else { base };
+        base + index as u16
+    }
+
+    /// Whether `addr`, below the OAM, is on the bus the transfer reads from, the cpu gets the byte being copied
+    /// instead. The cartridge and the WRAM share the external bus, the VRAM has its own.
+    pub fn conflicts(&self, addr: u16) -> bool {
+        is_video_bus(addr) == is_video_bus(self.source_addr(0))
+    }
+}
+
+fn is_video_bus(addr: u16) -> bool {
+    (0x8000..=0x9FFF).contains(&addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_takes_the_bus_after_the_start_delay() {
+        let mut dma = OamDma::default();
+        dma.start(0xC0);
+        // The instruction writing 0xFF46
+        assert_eq!(dma.tick(12), 0..0);
+        assert!(!dma.active());
+        assert_eq!(dma.tick(4), 0..0);
+        assert!(dma.active());
+        assert_eq!(dma.tick(640), 0..LENGTH);
+        assert!(!dma.active());
+    }
+
+    #[test]
+    fn restart_keeps_the_bus_blocked() {
+        let mut dma = OamDma::default();
+        dma.start(0xC0);
+        dma.tick(4);
+        assert_eq!(dma.tick(4 + 40), 0..10);
+        assert!(dma.active());
+
+        dma.start(0xD0);
+        assert_eq!(dma.tick(12), 0..0);
+        assert!(dma.active());
+        // In the start delay of the new transfer
+        assert_eq!(dma.tick(2), 0..0);
+        assert!(dma.active());
+        assert_eq!(dma.source_addr(0), 0xD000);
+        assert_eq!(dma.tick(2 + 640), 0..LENGTH);
+        assert!(!dma.active());
+    }
+}
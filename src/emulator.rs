@@ -0,0 +1,150 @@
+use crate::boot::{self, Model};
+use crate::cartridge::{CartridgeHeader, CgbSupport};
+use crate::cpu::{Cpu, Step};
+use crate::error::EmulationError;
+use crate::input::{ImageSource, Tilt};
+use crate::mbc::RtcClock;
+use crate::mmu::MMU;
+use crate::ppu::{Ppu, Renderer};
+
+/// A GameBoy with a cartridge inserted, entry point for the frontends.
+pub struct Emulator {
+    pub cpu: Cpu,
+    pub mmu: MMU,
+    pub model: Model,
+}
+
+impl Emulator {
+    /// Power on a DMG with the given ROM inserted, the built-in boot rom runs first.
+    pub fn new(rom: &[u8]) -> Result<Self, EmulationError> {
+        Self::with_model(rom, Model::Dmg, None)
+    }
+
+    /// Power on the given model with the given ROM inserted. The boot rom image runs first when
+    /// one is provided, otherwise the DMG runs the built-in boot rom and the other models start
+    /// in the state their boot rom leaves.
+    pub fn with_model(rom: &[u8], model: Model, boot_rom: Option<&[u8]>) -> Result<Self, EmulationError> {
+        Self::with_renderer(rom, model, boot_rom, Renderer::default())
+    }
+
+    /// Same as `with_model` with the lines drawn by `renderer`.
+    pub fn with_renderer(
+        rom: &[u8],
+        model: Model,
+        boot_rom: Option<&[u8]>,
+        renderer: Renderer,
+    ) -> Result<Self, EmulationError> {
+        let mut mmu = MMU::default();
+        mmu.ppu = Ppu::new(renderer);
+        mmu.load_rom(rom)?;
+        mmu.cgb_mode = model.is_cgb()
+            && mmu.cartridge.as_ref().is_some_and(|c| c.header.cgb != CgbSupport::None);
+        mmu.ppu.cgb_mode = mmu.cgb_mode;
+        let mut emulator = Emulator {
+            cpu: Cpu::default(),
+            mmu,
+            model,
+        };
+        match boot_rom {
+            Some(boot_rom) => emulator.mmu.load_boot_rom(model, boot_rom)?,
+            None if model == Model::Dmg => {}
+            None => emulator.skip_boot(),
+        }
+        Ok(emulator)
+    }
+
+    /// Header of the inserted cartridge.
+    pub fn header(&self) -> &CartridgeHeader {
+        &self.mmu.cartridge.as_ref().expect("a cartridge is always inserted").header
+    }
+
+    /// The last frame drawn, `SCREEN_WIDTH` x `SCREEN_HEIGHT` shades row by row: 0 is white and
+    /// 3 is black.
+    pub fn frame(&self) -> &[u8] {
+        self.mmu.ppu.frame()
+    }
+
+    /// Battery backed RAM of the cartridge followed by its clock or flash state, in the `.sav`
+    /// format shared with the other emulators. None when the cartridge has no battery.
+    pub fn save_data(&self) -> Option<Vec<u8>> {
+        self.mmu.cartridge.as_ref().filter(|c| c.has_battery()).map(|c| c.save_data())
+    }
+
+    /// Restore the RAM of the cartridge from a save produced by `save_data`.
+    pub fn load_save_data(&mut self, data: &[u8]) -> Result<(), EmulationError> {
+        match self.mmu.cartridge.as_mut() {
+            Some(cartridge) => cartridge.load_save_data(data),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the battery backed RAM, clock or flash of the cartridge changed since the last call,
+    /// the frontends save when the changes stop.
+    pub fn take_save_modified(&mut self) -> bool {
+        self.mmu.cartridge.as_mut().is_some_and(|c| c.has_battery() && c.take_save_modified())
+    }
+
+    /// State of the rumble motor of the cartridge, for the frontends able to vibrate.
+    pub fn rumble(&self) -> bool {
+        self.mmu.cartridge.as_ref().is_some_and(|c| c.rumble())
+    }
+
+    /// Tilt of the console, read by the cartridges with an accelerometer.
+    pub fn set_tilt(&mut self, tilt: Tilt) {
+        if let Some(cartridge) = self.mmu.cartridge.as_mut() {
+            cartridge.set_tilt(tilt);
+        }
+    }
+
+    /// State of the infrared LED of the cartridge.
+    pub fn ir_led(&self) -> bool {
+        self.mmu.cartridge.as_ref().is_some_and(|c| c.ir_led())
+    }
+
+    /// Light received by the infrared sensor of the cartridge.
+    pub fn set_ir_light(&mut self, light: bool) {
+        if let Some(cartridge) = self.mmu.cartridge.as_mut() {
+            cartridge.set_ir_light(light);
+        }
+    }
+
+    /// Source of the images captured by the camera cartridge.
+    pub fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        if let Some(cartridge) = self.mmu.cartridge.as_mut() {
+            cartridge.set_image_source(source);
+        }
+    }
+
+    /// Select what drives the real time clock of the cartridge, if it has one.
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        if let Some(cartridge) = self.mmu.cartridge.as_mut() {
+            cartridge.set_rtc_clock(clock);
+        }
+    }
+
+    /// Unmap the boot rom and start directly at the cartridge entry point, with the registers
+    /// and IO left by the boot rom of the model.
+    pub fn skip_boot(&mut self) {
+        boot::skip_boot(&mut self.cpu, &mut self.mmu, self.model);
+    }
+
+    /// Execute one instruction or service one interrupt.
+    pub fn step(&mut self) -> Result<Step, EmulationError> {
+        self.cpu.step(&mut self.mmu)
+    }
+
+    /// Run at least `cycles` cycles, returns the number of cycles actually run.
+    pub fn run_cycles(&mut self, cycles: u64) -> Result<u64, EmulationError> {
+        self.cpu.run_cycles(&mut self.mmu, cycles)
+    }
+
+    /// Run for the duration of one frame.
+    pub fn run_frame(&mut self) -> Result<u64, EmulationError> {
+        self.cpu.run_frame(&mut self.mmu)
+    }
+
+    /// Run until the next VBlank, or one frame when the lcd is off.
+    pub fn run_until_vblank(&mut self) -> Result<u64, EmulationError> {
+        self.cpu.run_until_vblank(&mut self.mmu)
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+/// Errors reported by the emulator instead of aborting the host application.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EmulationError {
+    /// The cpu fetched one of the opcodes that don't exist on the SM83
+    /// (0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD).
+    IllegalOpcode { opcode: u8, addr: u16 },
+    /// The cartridge type byte (0x0147) names a controller that isn't emulated.
+    UnsupportedCartridge(u8),
+    /// The ROM image doesn't fit in the address space of its cartridge.
+    RomTooLarge { size: usize, max: usize },
+    /// The ROM image is too small to contain a cartridge header.
+    RomTooSmall(usize),
+    /// A cartridge header field holds a value no cartridge uses.
+    InvalidHeader { field: &'static str, value: u8 },
+    /// The header checksum (0x014D) doesn't match the header, the boot ROM locks up on it.
+    HeaderChecksum { expected: u8, computed: u8 },
+    /// The boot ROM image doesn't have the size of the boot ROM of the model.
+    InvalidBootRom { size: usize, expected: usize },
+    /// The save doesn't have the size of the cartridge RAM and clock or flash.
+    InvalidSaveSize { size: usize, expected: usize },
+}
+
+impl fmt::Display for EmulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmulationError::IllegalOpcode { opcode, addr } => {
+                write!(f, "illegal opcode {:#04x} at {:#06x}", opcode, addr)
+            }
+            EmulationError::UnsupportedCartridge(kind) => {
+                write!(f, "unsupported cartridge type {:#04x}", kind)
+            }
+            EmulationError::RomTooLarge { size, max } => {
+                write!(f, "rom of {} bytes is larger than the {} bytes supported", size, max)
+            }
+            EmulationError::RomTooSmall(size) => {
+                write!(f, "rom of {} bytes is too small to contain a cartridge header", size)
+            }
+            EmulationError::InvalidHeader { field, value } => {
+                write!(f, "invalid {} {:#04x} in the cartridge header", field, value)
+            }
+            EmulationError::HeaderChecksum { expected, computed } => {
+                write!(f, "header checksum {:#04x} doesn't match the computed {:#04x}", expected, computed)
+            }
+            EmulationError::InvalidBootRom { size, expected } => {
+                write!(f, "boot rom of {} bytes, expected {} bytes", size, expected)
+            }
+            EmulationError::InvalidSaveSize { size, expected } => {
+                write!(f, "save of {} bytes, expected {} bytes", size, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EmulationError {}
@@ -0,0 +1,20 @@
+//! Inputs the frontend feeds to the cartridges with sensors.
+
+/// Width of the images captured by the Game Boy Camera.
+pub const CAMERA_WIDTH: usize = 128;
+/// Height of the images captured by the Game Boy Camera.
+pub const CAMERA_HEIGHT: usize = 112;
+
+/// Tilt of the console read by the MBC7 accelerometer, in g on each axis: positive x when tilted
+/// to the right, positive y when tilted toward the player.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tilt {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Source of the images captured by the Game Boy Camera, a webcam or a still image.
+pub trait ImageSource {
+    /// Grayscale image of `CAMERA_WIDTH` x `CAMERA_HEIGHT` pixels row by row, 0 is black.
+    fn capture(&mut self) -> Vec<u8>;
+}
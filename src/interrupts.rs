@@ -0,0 +1,35 @@
+use bitflags::bitflags;
+
+/// Address of the interrupt flag register (IF).
+pub const IF_ADDR: u16 = 0xFF0F;
+/// Address of the interrupt enable register (IE).
+pub const IE_ADDR: u16 = 0xFFFF;
+
+bitflags! {
+    /// Interrupt sources as laid out in the IE and IF registers, bit 0 has the highest priority.
+    pub struct Interrupt: u8 {
+        const VBLANK = 0x01;
+        const STAT = 0x02;
+        const TIMER = 0x04;
+        const SERIAL = 0x08;
+        const JOYPAD = 0x10;
+    }
+}
+
+impl Interrupt {
+    /// Returns the pending interrupt with the highest priority, if any.
+    pub fn highest_priority(pending: u8) -> Option<Interrupt> {
+        let pending = pending & Interrupt::all().bits();
+        if pending == 0 {
+            None
+        } else {
+            // Isolate the lowest set bit
+            Interrupt::from_bits(pending & pending.wrapping_neg())
+        }
+    }
+
+    /// Address of the handler jumped to when servicing the interrupt.
+    pub fn vector(self) -> u16 {
+        0x40 + 8 * self.bits().trailing_zeros() as u16
+    }
+}
@@ -0,0 +1,20 @@
+//! GameBoy emulator core, the `Emulator` type owns every component of the console.
+
+mod alu;
+pub mod boot;
+pub mod cartridge;
+pub mod cpu;
+pub mod dma;
+pub mod emulator;
+pub mod error;
+pub mod input;
+pub mod interrupts;
+pub mod mbc;
+pub mod mmu;
+pub mod op_codes;
+pub mod ppu;
+pub mod timer;
+
+pub use boot::Model;
+pub use emulator::Emulator;
+pub use error::EmulationError;
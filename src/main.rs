@@ -1,20 +1,236 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
+
 use log::*;
 
-use cpu::Cpu;
-use mmu::MMU;
+use risualboy::ppu::{Renderer, SCREEN_HEIGHT, SCREEN_WIDTH};
+use risualboy::{Emulator, Model};
+
+/// Duration of a frame on the real hardware, ~59.73 frames per second.
+const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
+/// Frames without save changes before the save is written, the games write their saves in bursts.
+const SAVE_DELAY_FRAMES: u64 = 60;
+/// Gray levels of the 4 DMG shades in the screenshots.
+const SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+const USAGE: &str = "\
+Usage: risualboy [OPTIONS] <ROM>
+
+Arguments:
+  <ROM>                    Path of the cartridge image to run
+
+Options:
+      --model <MODEL>      dmg, mgb, sgb or cgb (default: dmg)
+      --boot-rom <PATH>    Boot rom image of the model, only the DMG one is built in
+      --skip-boot          Start directly at the cartridge entry point
+      --renderer <KIND>    scanline or fifo, fifo is slower but handles mid line effects
+                           (default: scanline)
+      --log-level <LEVEL>  off, error, warn, info, debug or trace (default: warn, or RUST_LOG)
+      --frames <N>         Stop after N frames
+      --headless           Don't pace the emulation to the real hardware speed
+      --screenshot <PATH>  Save the last frame to PATH on exit, as a binary PGM image
+  -h, --help               Print this help
+
+There is no sound output, the APU is not emulated yet.
+";
+
+#[derive(Debug, Default)]
+struct Args {
+    rom: PathBuf,
+    model: Option<Model>,
+    boot_rom: Option<PathBuf>,
+    skip_boot: bool,
+    renderer: Renderer,
+    log_level: Option<LevelFilter>,
+    frames: Option<u64>,
+    headless: bool,
+    screenshot: Option<PathBuf>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut rom = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("missing value for {}", name));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            "--model" => {
+                let model = value(&arg)?;
+                parsed.model = Some(match model.to_ascii_lowercase().as_str() {
+                    "dmg" => Model::Dmg,
+                    "mgb" => Model::Mgb,
+                    "sgb" => Model::Sgb,
+                    "cgb" => Model::Cgb,
+                    _ => return Err(format!("invalid model: {}", model)),
+                });
+            }
+            "--boot-rom" => parsed.boot_rom = Some(value(&arg)?.into()),
+            "--skip-boot" => parsed.skip_boot = true,
+            "--renderer" => {
+                let renderer = value(&arg)?;
+                parsed.renderer = match renderer.to_ascii_lowercase().as_str() {
+                    "scanline" => Renderer::Scanline,
+                    "fifo" => Renderer::PixelFifo,
+                    _ => return Err(format!("invalid renderer: {}", renderer)),
+                };
+            }
+            "--log-level" => {
+                let level = value(&arg)?;
+                let level = level.parse().map_err(|_| format!("invalid log level: {}", level))?;
+                parsed.log_level = Some(level);
+            }
+            "--frames" => {
+                let frames = value(&arg)?;
+                let frames = frames.parse().map_err(|_| format!("invalid frame count: {}", frames))?;
+                parsed.frames = Some(frames);
+            }
+            "--headless" => parsed.headless = true,
+            "--screenshot" => parsed.screenshot = Some(value(&arg)?.into()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ if rom.is_some() => return Err(format!("unexpected argument: {}", arg)),
+            _ => rom = Some(PathBuf::from(arg)),
+        }
+    }
+    parsed.rom = rom.ok_or("missing ROM path")?;
+    if parsed.boot_rom.is_some() && parsed.skip_boot {
+        return Err("--boot-rom and --skip-boot can't be used together".to_string());
+    }
+    Ok(parsed)
+}
+
+fn read_file(kind: &str, path: &PathBuf) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|e| {
+        error!("cannot read {} {}: {}", kind, path.display(), e);
+        process::exit(1);
+    })
+}
 
-use crate::op_codes::OP_CODES;
+/// Load the save of the cartridge if there is one, false when the existing save can't be used
+/// and must not be overwritten.
+fn load_save(emulator: &mut Emulator, path: &Path) -> bool {
+    if emulator.save_data().is_none() || !path.exists() {
+        return true;
+    }
+    let loaded = fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| emulator.load_save_data(&data).map_err(|e| e.to_string()));
+    match loaded {
+        Ok(()) => {
+            info!("Loaded save {}", path.display());
+            true
+        }
+        Err(e) => {
+            error!("cannot load save {}: {}, it will not be overwritten", path.display(), e);
+            false
+        }
+    }
+}
+
+/// Write the save through a temporary file so an interrupted write doesn't lose the previous one.
+fn write_save(emulator: &Emulator, path: &Path) {
+    let Some(data) = emulator.save_data() else {
+        return;
+    };
+    let tmp = path.with_extension("sav.tmp");
+    match fs::write(&tmp, data).and_then(|_| fs::rename(&tmp, path)) {
+        Ok(()) => debug!("Saved {}", path.display()),
+        Err(e) => error!("cannot write save {}: {}", path.display(), e),
+    }
+}
 
-mod mmu;
-mod op_codes;
-pub mod cpu;
+/// Write the frame as a binary PGM image, readable by most image viewers and converters.
+fn write_screenshot(frame: &[u8], path: &Path) -> std::io::Result<()> {
+    let mut image = format!("P5\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT).into_bytes();
+    image.extend(frame.iter().map(|&shade| SHADES[shade as usize]));
+    fs::write(path, image)
+}
 
 fn main() {
-    let tetris_rom = include_bytes!("../tetris.gb");
-    env_logger::builder().filter_level(LevelFilter::Debug).init();
+    let args = parse_args(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("error: {}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    let mut logger = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"));
+    if let Some(level) = args.log_level {
+        logger.filter_level(level);
+    }
+    logger.init();
+
+    let rom = read_file("rom", &args.rom);
+    let boot_rom = args.boot_rom.as_ref().map(|path| read_file("boot rom", path));
+    let model = args.model.unwrap_or(Model::Dmg);
+    let mut emulator = Emulator::with_renderer(&rom, model, boot_rom.as_deref(), args.renderer).unwrap_or_else(|e| {
+        error!("cannot start {}: {}", args.rom.display(), e);
+        process::exit(1);
+    });
+    let header = emulator.header();
+    info!(
+        "Loaded {:?} ({:?}, {} KiB rom, {} KiB ram)",
+        header.title,
+        header.cartridge_type.mapper,
+        header.rom_size / 1024,
+        header.ram_size / 1024
+    );
+    if args.skip_boot {
+        emulator.skip_boot();
+    }
+    let save_path = args.rom.with_extension("sav");
+    let save_enabled = load_save(&mut emulator, &save_path);
+
+    // Stop at the end of the frame on Ctrl-C or SIGTERM, so the save is written like on a normal
+    // exit
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    if let Err(e) = ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst)) {
+        warn!("cannot handle Ctrl-C, interrupting will lose the unsaved game: {}", e);
+    }
+
+    let mut frames = 0;
+    let mut last_save_write = None;
+    let mut next_frame = Instant::now();
+    while running.load(Ordering::SeqCst) && args.frames.is_none_or(|max| frames < max) {
+        if let Err(e) = emulator.run_frame() {
+            error!("{}", e);
+            if save_enabled {
+                write_save(&emulator, &save_path);
+            }
+            process::exit(1);
+        }
+        frames += 1;
+        if emulator.take_save_modified() {
+            last_save_write = Some(frames);
+        }
+        if save_enabled && last_save_write.is_some_and(|frame| frames - frame >= SAVE_DELAY_FRAMES) {
+            write_save(&emulator, &save_path);
+            last_save_write = None;
+        }
+        if !args.headless {
+            next_frame += FRAME_DURATION;
+            let now = Instant::now();
+            if next_frame > now {
+                std::thread::sleep(next_frame - now);
+            } else {
+                // Running late, don't try to catch up
+                next_frame = now;
+            }
+        }
+    }
+    info!("Ran {} frames", frames);
+    if save_enabled {
+        write_save(&emulator, &save_path);
+    }
 
-    let mut cpu = Cpu::default();
-    let mut mmu = MMU::default();
-    mmu.load_rom(tetris_rom);
-    cpu.run(&mut mmu);
+    if let Some(path) = &args.screenshot {
+        if let Err(e) = write_screenshot(emulator.frame(), path) {
+            error!("cannot write screenshot {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
 }
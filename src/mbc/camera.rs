@@ -0,0 +1,139 @@
+use crate::input::{ImageSource, CAMERA_HEIGHT, CAMERA_WIDTH};
+use crate::mbc::{ram_offset, rom_byte, store, Mbc};
+
+const REGISTERS: usize = 0x36;
+/// Offset of the captured image in the RAM bank 0, as 16x14 tiles.
+const IMAGE_OFFSET: usize = 0x100;
+/// Offset of the 4x4 dithering matrix in the registers, 3 thresholds per pixel.
+const DITHER_MATRIX: usize = 0x06;
+/// Duration of a capture without exposure time.
+const CAPTURE_CYCLES: u32 = 32446 * 4;
+/// Exposure giving the pixels of the source unchanged.
+const NEUTRAL_EXPOSURE: u32 = 0x1000;
+
+/// Game Boy Camera (Pocket Camera), up to 1 MiB of ROM, 128 KiB of RAM and the M64282FP image
+/// sensor, the images come from the source set by the frontend.
+///
+/// Setting bit 4 of the RAM bank register maps the sensor registers over 0xA000-0xBFFF, writing 1
+/// to the register 0 starts a capture which clears it once done. Registers 2 and 3 hold the
+/// exposure time and registers 6-0x35 the dithering matrix turning the sensor output into 2 bit
+/// pixels. The RAM is always readable, only the writes need to be enabled.
+pub struct PocketCamera {
+    ram_enabled: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    registers_mapped: bool,
+    registers: [u8; REGISTERS],
+    /// Cycles left before the capture in progress completes
+    capture_cycles: u32,
+    source: Option<Box<dyn ImageSource>>,
+}
+
+impl PocketCamera {
+    pub fn new() -> Self {
+        PocketCamera {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            registers_mapped: false,
+            registers: [0; REGISTERS],
+            capture_cycles: 0,
+            source: None,
+        }
+    }
+
+    fn exposure(&self) -> u32 {
+        u16::from_be_bytes([self.registers[2], self.registers[3]]) as u32
+    }
+
+    /// Capture an image and write it in the RAM as tiles. The games wait for the end of the
+    /// capture before reading it, so the image is written as soon as the capture starts.
+    fn capture(&mut self, ram: &mut [u8]) {
+        self.capture_cycles = CAPTURE_CYCLES + self.exposure() * 64;
+        let image = self
+            .source
+            .as_mut()
+            .map(|source| source.capture())
+            .filter(|image| image.len() == CAMERA_WIDTH * CAMERA_HEIGHT)
+            .unwrap_or_else(|| vec![0x80; CAMERA_WIDTH * CAMERA_HEIGHT]);
+        let exposure = self.exposure();
+        for y in 0..CAMERA_HEIGHT {
+            for x in 0..CAMERA_WIDTH {
+                let value = (image[y * CAMERA_WIDTH + x] as u32 * exposure / NEUTRAL_EXPOSURE).min(0xFF) as u8;
+                let thresholds = DITHER_MATRIX + ((y & 3) * 4 + (x & 3)) * 3;
+                let color = 3 - self.registers[thresholds..thresholds + 3].iter().filter(|&&t| value >= t).count() as u8;
+                let tile = (y / 8) * (CAMERA_WIDTH / 8) + x / 8;
+                let offset = IMAGE_OFFSET + tile * 16 + (y % 8) * 2;
+                let bit = 7 - (x % 8);
+                if offset + 1 < ram.len() {
+                    ram[offset] = (ram[offset] & !(1 << bit)) | (color & 1) << bit;
+                    ram[offset + 1] = (ram[offset + 1] & !(1 << bit)) | (color >> 1) << bit;
+                }
+            }
+        }
+    }
+}
+
+impl Default for PocketCamera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc for PocketCamera {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        rom_byte(rom, bank, addr)
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = val & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = val & 0x3F,
+            0x4000..=0x5FFF => {
+                self.registers_mapped = val & 0x10 != 0;
+                self.ram_bank = val & 0x0F;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if self.registers_mapped {
+            // Only the register 0 can be read back
+            return match addr & 0x7F {
+                0 => (self.registers[0] & 0x06) | (self.capture_cycles > 0) as u8,
+                _ => 0x00,
+            };
+        }
+        ram_offset(ram, self.ram_bank as usize, addr).map_or(0xFF, |offset| ram[offset])
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if self.registers_mapped {
+            let register = (addr & 0x7F) as usize;
+            if register < REGISTERS {
+                self.registers[register] = val;
+            }
+            // The image goes to the capture area of the RAM, overwritten by every capture of the
+            // viewfinder, the games copy it elsewhere to keep it
+            if register == 0 && val & 1 != 0 && self.capture_cycles == 0 {
+                self.capture(ram);
+            }
+            return false;
+        }
+        match ram_offset(ram, self.ram_bank as usize, addr) {
+            Some(offset) if self.ram_enabled => store(&mut ram[offset], val),
+            _ => false,
+        }
+    }
+
+    fn tick(&mut self, cycles: u16) {
+        self.capture_cycles = self.capture_cycles.saturating_sub(cycles as u32);
+    }
+
+    fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.source = Some(source);
+    }
+}
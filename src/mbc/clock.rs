@@ -0,0 +1,77 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Cycles per second of the DMG clock, the cartridge clocks have their own 32768Hz crystal.
+const CYCLES_PER_SECOND: u32 = 4_194_304;
+
+/// What makes the real time clocks of the cartridges advance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RtcClock {
+    /// The time of the host, the clock keeps running while the emulator is closed
+    Host,
+    /// The emulated cycles, deterministic but stops with the emulation
+    Emulated,
+}
+
+/// Counts the seconds elapsed for a cartridge clock from its source.
+#[derive(Clone, Debug)]
+pub(crate) struct Clock {
+    source: RtcClock,
+    /// Cycles elapsed since the last second
+    sub_second: u32,
+    /// Unix time of the last synchronisation with the host clock
+    last_sync: u64,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Clock { source: RtcClock::Host, sub_second: 0, last_sync: unix_time() }
+    }
+
+    /// Seconds elapsed in the `cycles` just emulated.
+    pub fn tick(&mut self, cycles: u16) -> u64 {
+        if self.source != RtcClock::Emulated {
+            return 0;
+        }
+        self.sub_second += cycles as u32;
+        let seconds = self.sub_second / CYCLES_PER_SECOND;
+        self.sub_second %= CYCLES_PER_SECOND;
+        seconds as u64
+    }
+
+    /// Seconds elapsed on the host since the last call.
+    pub fn sync(&mut self) -> u64 {
+        if self.source != RtcClock::Host {
+            return 0;
+        }
+        let now = unix_time();
+        let elapsed = now.saturating_sub(self.last_sync);
+        self.last_sync = now;
+        elapsed
+    }
+
+    /// Change the source, the caller syncs first so no time is lost or counted twice.
+    pub fn set_source(&mut self, source: RtcClock) {
+        self.source = source;
+        self.last_sync = unix_time();
+    }
+
+    /// Restart the current second, done by the writes to the seconds register.
+    pub fn reset_sub_second(&mut self) {
+        self.sub_second = 0;
+    }
+
+    /// Unix time stored in the saves, the one of the last `sync` as the counters of the clock
+    /// haven't counted the time since.
+    pub fn timestamp(&self) -> u64 {
+        self.last_sync
+    }
+
+    /// Restore the unix time of a save, the next `sync` counts the time since.
+    pub fn set_timestamp(&mut self, timestamp: u64) {
+        self.last_sync = timestamp;
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
@@ -0,0 +1,66 @@
+use crate::mbc::{ram_offset, rom_byte, store, Mbc};
+
+/// Hudson HuC1, up to 1 MiB of ROM, 32 KiB of RAM and an infrared transceiver.
+///
+/// Writing 0x0E to 0x0000-0x1FFF maps the transceiver over the RAM: bit 0 of the writes drives
+/// the LED and the reads return 0xC1 when light is received, 0xC0 otherwise.
+pub struct HuC1 {
+    ir_mode: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    ir_led: bool,
+    ir_light: bool,
+}
+
+impl HuC1 {
+    pub fn new() -> Self {
+        HuC1 { ir_mode: false, rom_bank: 1, ram_bank: 0, ir_led: false, ir_light: false }
+    }
+}
+
+impl Default for HuC1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc for HuC1 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        rom_byte(rom, bank, addr)
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        match addr {
+            0x0000..=0x1FFF => self.ir_mode = val & 0x0F == 0x0E,
+            0x2000..=0x3FFF => self.rom_bank = val & 0x3F,
+            0x4000..=0x5FFF => self.ram_bank = val & 0x03,
+            _ => {}
+        }
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if self.ir_mode {
+            return 0xC0 | self.ir_light as u8;
+        }
+        // The RAM has no enable register
+        ram_offset(ram, self.ram_bank as usize, addr).map_or(0xFF, |offset| ram[offset])
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if self.ir_mode {
+            self.ir_led = val & 1 != 0;
+            return false;
+        }
+        ram_offset(ram, self.ram_bank as usize, addr).is_some_and(|offset| store(&mut ram[offset], val))
+    }
+
+    fn ir_led(&self) -> bool {
+        self.ir_led
+    }
+
+    fn set_ir_light(&mut self, light: bool) {
+        self.ir_light = light;
+    }
+}
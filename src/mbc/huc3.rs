@@ -0,0 +1,269 @@
+use log::*;
+
+use crate::mbc::clock::Clock;
+use crate::mbc::{ram_offset, rom_byte, store, Mbc, RtcClock};
+
+/// Size of the HuC3 clock state stored after the RAM, the format used by SameBoy: the unix time
+/// of the save as a u64, the minutes, days, alarm minutes and alarm days as u16 and the alarm
+/// enable as a u8, all little endian.
+pub const HUC3_RTC_SAVE_SIZE: usize = 17;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+/// Nibbles of the clock memory holding the alarm minutes, days and enable.
+const ALARM_MINUTES: usize = 0x58;
+const ALARM_DAYS: usize = 0x5B;
+const ALARM_ENABLE: usize = 0x5F;
+
+/// Hudson HuC3, up to 2 MiB of ROM, 32 KiB of RAM, an infrared transceiver and a real time
+/// clock counting minutes and days.
+///
+/// The write to 0x0000-0x1FFF selects what 0xA000-0xBFFF maps: the RAM (0x0A, 0x00 read only),
+/// the clock command register (0x0B), the clock response (0x0C), the command semaphore (0x0D)
+/// or the transceiver (0x0E).
+/// The clock is driven by commands: the upper nibble is the command, the lower the argument.
+/// They access a memory of 256 nibbles, the first 7 are the minutes and days counters.
+pub struct HuC3 {
+    mode: u8,
+    rom_bank: u8,
+    ram_bank: u8,
+    /// Minute of the day, 0-1439
+    minutes: u16,
+    days: u16,
+    /// Seconds elapsed in the current minute
+    seconds: u64,
+    memory: [u8; 0x100],
+    index: u8,
+    response: u8,
+    clock: Clock,
+    ir_led: bool,
+    ir_light: bool,
+}
+
+impl HuC3 {
+    pub fn new() -> Self {
+        HuC3 {
+            mode: 0,
+            rom_bank: 1,
+            ram_bank: 0,
+            minutes: 0,
+            days: 0,
+            seconds: 0,
+            memory: [0; 0x100],
+            index: 0,
+            response: 0,
+            clock: Clock::new(),
+            ir_led: false,
+            ir_light: false,
+        }
+    }
+
+    fn advance(&mut self, seconds: u64) {
+        self.seconds += seconds;
+        let minutes = self.minutes as u64 + self.seconds / 60;
+        self.seconds %= 60;
+        self.minutes = (minutes % MINUTES_PER_DAY as u64) as u16;
+        self.days = self.days.wrapping_add((minutes / MINUTES_PER_DAY as u64) as u16);
+    }
+
+    fn sync(&mut self) {
+        let seconds = self.clock.sync();
+        self.advance(seconds);
+    }
+
+    /// Nibble of the clock memory, the minutes and days counters are read live.
+    fn read_nibble(&self, index: u8) -> u8 {
+        match index {
+            0..=2 => (self.minutes >> (index * 4)) as u8 & 0x0F,
+            3..=6 => (self.days >> ((index - 3) * 4)) as u8 & 0x0F,
+            _ => self.memory[index as usize],
+        }
+    }
+
+    /// Returns whether the nibble changed.
+    fn write_nibble(&mut self, index: u8, val: u8) -> bool {
+        let changed = self.read_nibble(index) != val;
+        match index {
+            0..=2 => {
+                let shift = index * 4;
+                self.minutes = (self.minutes & !(0x0F << shift)) | (val as u16) << shift;
+                self.seconds = 0;
+                self.clock.reset_sub_second();
+            }
+            3..=6 => {
+                let shift = (index - 3) * 4;
+                self.days = (self.days & !(0x0F << shift)) | (val as u16) << shift;
+            }
+            _ => self.memory[index as usize] = val,
+        }
+        changed
+    }
+
+    /// Returns whether the clock memory changed.
+    fn command(&mut self, val: u8) -> bool {
+        self.sync();
+        let arg = val & 0x0F;
+        match val >> 4 {
+            // Read and increment the index
+            0x1 => {
+                self.response = 0x10 | self.read_nibble(self.index);
+                self.index = self.index.wrapping_add(1);
+            }
+            // Write, 0x3 also increments the index
+            0x2 => return self.write_nibble(self.index, arg),
+            0x3 => {
+                let changed = self.write_nibble(self.index, arg);
+                self.index = self.index.wrapping_add(1);
+                return changed;
+            }
+            0x4 => self.index = (self.index & 0xF0) | arg,
+            0x5 => self.index = (self.index & 0x0F) | arg << 4,
+            0x6 => match arg {
+                // The counters are always accessed live, latching and setting the time are
+                // done by the nibble accesses
+                0x0 | 0x1 => {}
+                // Status, the clock is always ready
+                0x2 => self.response = 0x01,
+                0xE => debug!("HuC3 tone generator is not emulated"),
+                _ => debug!("Unknown HuC3 extended command {:#03x}", arg),
+            },
+            _ => debug!("Unknown HuC3 command {:#04x}", val),
+        }
+        false
+    }
+
+    fn alarm(&self) -> (u16, u16, bool) {
+        let nibbles = |start: usize, count: usize| {
+            (0..count).fold(0, |acc, i| acc | (self.memory[start + i] as u16) << (i * 4))
+        };
+        (nibbles(ALARM_MINUTES, 3), nibbles(ALARM_DAYS, 4), self.memory[ALARM_ENABLE] & 1 != 0)
+    }
+
+    fn set_alarm(&mut self, minutes: u16, days: u16, enabled: bool) {
+        for i in 0..3 {
+            self.memory[ALARM_MINUTES + i] = (minutes >> (i * 4)) as u8 & 0x0F;
+        }
+        for i in 0..4 {
+            self.memory[ALARM_DAYS + i] = (days >> (i * 4)) as u8 & 0x0F;
+        }
+        self.memory[ALARM_ENABLE] = enabled as u8;
+    }
+}
+
+impl Default for HuC3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc for HuC3 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        rom_byte(rom, bank, addr)
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        match addr {
+            0x0000..=0x1FFF => self.mode = val & 0x0F,
+            0x2000..=0x3FFF => self.rom_bank = val & 0x7F,
+            0x4000..=0x5FFF => self.ram_bank = val & 0x03,
+            _ => {}
+        }
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        match self.mode {
+            0x0 | 0xA => ram_offset(ram, self.ram_bank as usize, addr).map_or(0xFF, |offset| ram[offset]),
+            0xC => self.response,
+            // The commands complete at once
+            0xD => 0x01,
+            0xE => 0xC0 | self.ir_light as u8,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        match self.mode {
+            0xA => ram_offset(ram, self.ram_bank as usize, addr).is_some_and(|offset| store(&mut ram[offset], val)),
+            0xB => self.command(val),
+            0xE => {
+                self.ir_led = val & 1 != 0;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn tick(&mut self, cycles: u16) {
+        let seconds = self.clock.tick(cycles);
+        self.advance(seconds);
+    }
+
+    fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.sync();
+        self.clock.set_source(clock);
+    }
+
+    fn save_extra(&self) -> Option<Vec<u8>> {
+        let (alarm_minutes, alarm_days, alarm_enabled) = self.alarm();
+        let mut data = Vec::with_capacity(HUC3_RTC_SAVE_SIZE);
+        // The format has no seconds, they are kept by saving an earlier time
+        let timestamp = self.clock.timestamp().saturating_sub(self.seconds);
+        data.extend_from_slice(&timestamp.to_le_bytes());
+        data.extend_from_slice(&self.minutes.to_le_bytes());
+        data.extend_from_slice(&self.days.to_le_bytes());
+        data.extend_from_slice(&alarm_minutes.to_le_bytes());
+        data.extend_from_slice(&alarm_days.to_le_bytes());
+        data.push(alarm_enabled as u8);
+        Some(data)
+    }
+
+    fn load_extra(&mut self, data: &[u8]) {
+        if data.len() < HUC3_RTC_SAVE_SIZE {
+            warn!("HuC3 clock state of {} bytes ignored", data.len());
+            return;
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        let mut timestamp = [0; 8];
+        timestamp.copy_from_slice(&data[..8]);
+        self.clock.set_timestamp(u64::from_le_bytes(timestamp));
+        self.minutes = u16_at(8) % MINUTES_PER_DAY;
+        self.days = u16_at(10);
+        self.seconds = 0;
+        self.set_alarm(u16_at(12), u16_at(14), data[16] & 1 != 0);
+        // The time spent while the emulator was closed
+        self.sync();
+    }
+
+    fn extra_sizes(&self) -> &'static [usize] {
+        &[HUC3_RTC_SAVE_SIZE]
+    }
+
+    fn ir_led(&self) -> bool {
+        self.ir_led
+    }
+
+    fn set_ir_light(&mut self, light: bool) {
+        self.ir_light = light;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_keeps_time_since_last_sync() {
+        let mut mbc = HuC3::new();
+        // The last command was 100 seconds ago, 20 seconds into a minute
+        mbc.seconds = 20;
+        let timestamp = mbc.clock.timestamp();
+        mbc.clock.set_timestamp(timestamp - 100);
+
+        let save = mbc.save_extra().unwrap();
+        let mut loaded = HuC3::new();
+        loaded.load_extra(&save);
+        let seconds = loaded.minutes as u64 * 60 + loaded.seconds;
+        assert!((120..=122).contains(&seconds), "{} seconds", seconds);
+    }
+}
@@ -0,0 +1,192 @@
+use log::*;
+
+use crate::cartridge::ROM_BANK_SIZE;
+use crate::mbc::{ram_offset, rom_byte, store, Mbc};
+
+/// Offset of the Nintendo logo in the cartridge header.
+const LOGO: usize = 0x104;
+const LOGO_SIZE: usize = 0x30;
+
+/// MBC1, up to 2 MiB of ROM and 32 KiB of RAM.
+///
+/// The ROM bank is made of BANK1 (5 bits) and BANK2 (2 bits). In mode 1 BANK2 also selects the
+/// bank mapped at 0x0000-0x3FFF and the RAM bank.
+/// The MBC1M multicarts wire BANK1 on 4 bits only, so BANK2 selects one of four 256 KiB games.
+pub struct Mbc1 {
+    ram_enabled: bool,
+    bank1: u8,
+    bank2: u8,
+    mode: bool,
+    multicart: bool,
+}
+
+impl Mbc1 {
+    pub fn new(rom: &[u8]) -> Self {
+        let multicart = Self::is_multicart(rom);
+        if multicart {
+            info!("MBC1M multicart detected");
+        }
+        Mbc1 {
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            mode: false,
+            multicart,
+        }
+    }
+
+    /// The multicarts are 1 MiB and each of their games starts with a header, so the Nintendo
+    /// logo appears at the start of the banks 0x10, 0x20 and 0x30 too.
+    fn is_multicart(rom: &[u8]) -> bool {
+        if rom.len() != 64 * ROM_BANK_SIZE {
+            return false;
+        }
+        let logo = &rom[LOGO..LOGO + LOGO_SIZE];
+        (1..4)
+            .filter(|game| {
+                let start = game * 0x10 * ROM_BANK_SIZE + LOGO;
+                &rom[start..start + LOGO_SIZE] == logo
+            })
+            .count()
+            >= 2
+    }
+
+    /// Bits of the ROM bank number above BANK1.
+    fn upper_bits(&self) -> usize {
+        let shift = if self.multicart { 4 } else { 5 };
+        (self.bank2 as usize) << shift
+    }
+
+    fn ram_bank(&self) -> usize {
+        if self.mode {
+            self.bank2 as usize
+        } else {
+            0
+        }
+    }
+}
+
+impl Mbc for Mbc1 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = if addr < 0x4000 {
+            if self.mode {
+                self.upper_bits()
+            } else {
+                0
+            }
+        } else {
+            let bank1 = if self.multicart { self.bank1 & 0x0F } else { self.bank1 };
+            self.upper_bits() | bank1 as usize
+        };
+        rom_byte(rom, bank, addr)
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = val & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                // The bank 0 can't be selected, the 5 bits are checked before the wiring so
+                // 0x20, 0x40 and 0x60 can't be mapped at 0x4000 either
+                self.bank1 = val & 0x1F;
+                if self.bank1 == 0 {
+                    self.bank1 = 1;
+                }
+            }
+            0x4000..=0x5FFF => self.bank2 = val & 0x03,
+            _ => self.mode = val & 0x01 != 0,
+        }
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        match ram_offset(ram, self.ram_bank(), addr) {
+            Some(offset) if self.ram_enabled => ram[offset],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        match ram_offset(ram, self.ram_bank(), addr) {
+            Some(offset) if self.ram_enabled => store(&mut ram[offset], val),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ROM of `banks` banks starting with their number, with the logo at the start of the
+    /// `games` banks.
+    fn banked_rom(banks: usize, games: &[usize]) -> Vec<u8> {
+        let mut rom = vec![0; banks * ROM_BANK_SIZE];
+        for bank in 0..banks {
+            rom[bank * ROM_BANK_SIZE] = bank as u8;
+        }
+        for game in games {
+            let start = game * ROM_BANK_SIZE + LOGO;
+            rom[start..start + LOGO_SIZE].fill(0xCE);
+        }
+        rom
+    }
+
+    /// Register writes, address read and bank expected.
+    type Case = (&'static [(u16, u8)], u16, u8);
+
+    /// Bank mapped at `addr` after the register writes.
+    fn bank_after(rom: &[u8], writes: &[(u16, u8)], addr: u16) -> u8 {
+        let mut mbc = Mbc1::new(rom);
+        for &(reg, val) in writes {
+            mbc.write_rom(reg, val);
+        }
+        mbc.read_rom(rom, addr)
+    }
+
+    #[test]
+    fn rom_banking() {
+        let rom = banked_rom(128, &[]);
+        let cases: [Case; 11] = [
+            (&[], 0x4000, 0x01),
+            (&[(0x2000, 0x00)], 0x4000, 0x01),
+            (&[(0x2000, 0x1F)], 0x4000, 0x1F),
+            // Only the 5 lower bits are checked for 0, the banks 0x20, 0x40 and 0x60 can't be
+            // mapped at 0x4000
+            (&[(0x2000, 0x20)], 0x4000, 0x01),
+            (&[(0x4000, 0x01), (0x2000, 0x00)], 0x4000, 0x21),
+            (&[(0x4000, 0x02), (0x2000, 0x00)], 0x4000, 0x41),
+            (&[(0x4000, 0x03), (0x2000, 0x00)], 0x4000, 0x61),
+            (&[(0x4000, 0x03), (0x2000, 0x05)], 0x4000, 0x65),
+            // Mode 0 always maps the bank 0 at 0x0000
+            (&[(0x4000, 0x02)], 0x0000, 0x00),
+            // Mode 1 maps BANK2 at 0x0000 too
+            (&[(0x4000, 0x02), (0x6000, 0x01)], 0x0000, 0x40),
+            (&[(0x4000, 0x03), (0x6000, 0x01), (0x2000, 0x00)], 0x4000, 0x61),
+        ];
+        for (writes, addr, bank) in cases {
+            assert_eq!(bank_after(&rom, writes, addr), bank, "{:x?} then read {:#06x}", writes, addr);
+        }
+    }
+
+    #[test]
+    fn multicart_banking() {
+        let rom = banked_rom(64, &[0x00, 0x10, 0x20, 0x30]);
+        assert!(Mbc1::new(&rom).multicart);
+        assert!(!Mbc1::new(&banked_rom(64, &[0x00])).multicart);
+
+        let cases: [Case; 7] = [
+            (&[], 0x4000, 0x01),
+            // BANK1 on 4 bits, BANK2 selects the game
+            (&[(0x4000, 0x01), (0x2000, 0x02)], 0x4000, 0x12),
+            (&[(0x4000, 0x02), (0x2000, 0x05)], 0x4000, 0x25),
+            (&[(0x2000, 0x1F)], 0x4000, 0x0F),
+            // The bank 0 check is on 5 bits: BANK1 0x10 maps the first bank of the game
+            (&[(0x4000, 0x01), (0x2000, 0x10)], 0x4000, 0x10),
+            (&[(0x4000, 0x03), (0x6000, 0x01)], 0x0000, 0x30),
+            (&[(0x4000, 0x03)], 0x0000, 0x00),
+        ];
+        for (writes, addr, bank) in cases {
+            assert_eq!(bank_after(&rom, writes, addr), bank, "{:x?} then read {:#06x}", writes, addr);
+        }
+    }
+}
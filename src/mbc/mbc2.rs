@@ -0,0 +1,128 @@
+use crate::mbc::{rom_byte, store, Mbc};
+
+/// Size of the RAM built in the MBC2, 512 half bytes stored one per byte.
+pub const MBC2_RAM_SIZE: usize = 0x200;
+
+/// MBC2, up to 256 KiB of ROM and 512×4 bits of RAM inside the controller.
+///
+/// The controller only decodes address bit 8 in 0x0000-0x3FFF: clear it selects the RAM enable
+/// register, set it selects the ROM bank. The RAM is echoed all over 0xA000-0xBFFF.
+pub struct Mbc2 {
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+
+impl Mbc2 {
+    pub fn new() -> Self {
+        Mbc2 { ram_enabled: false, rom_bank: 1 }
+    }
+}
+
+impl Default for Mbc2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc for Mbc2 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        rom_byte(rom, bank, addr)
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        if addr >= 0x4000 {
+            return false;
+        }
+        if addr & 0x100 == 0 {
+            self.ram_enabled = val & 0x0F == 0x0A;
+        } else {
+            self.rom_bank = (val & 0x0F).max(1);
+        }
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        match ram.get(addr as usize & (MBC2_RAM_SIZE - 1)) {
+            // Only the lower nibble is connected, the upper one reads as open bus
+            Some(val) if self.ram_enabled => 0xF0 | val,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        match ram.get_mut(addr as usize & (MBC2_RAM_SIZE - 1)) {
+            Some(byte) if self.ram_enabled => store(byte, val & 0x0F),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::ROM_BANK_SIZE;
+
+    /// ROM of 16 banks starting with their number.
+    fn banked_rom() -> Vec<u8> {
+        let mut rom = vec![0; 16 * ROM_BANK_SIZE];
+        for bank in 0..16 {
+            rom[bank * ROM_BANK_SIZE] = bank as u8;
+        }
+        rom
+    }
+
+    #[test]
+    fn registers_selected_by_address_bit_8() {
+        // (address, value written, RAM enabled, bank at 0x4000)
+        let cases = [
+            (0x0000, 0x0A, true, 0x01),
+            (0x2000, 0x0A, true, 0x01),
+            (0x3EFF, 0x0A, true, 0x01),
+            (0x0000, 0x05, false, 0x01),
+            (0x0100, 0x05, false, 0x05),
+            (0x2100, 0x03, false, 0x03),
+            (0x3FFF, 0x0F, false, 0x0F),
+            // Bit 8 set selects the ROM bank even with 0x0A
+            (0x0100, 0x0A, false, 0x0A),
+            // Only 4 bits, the bank 0 maps the bank 1
+            (0x0100, 0x00, false, 0x01),
+            (0x0100, 0x10, false, 0x01),
+            (0x0100, 0x12, false, 0x02),
+            // Not a register
+            (0x4100, 0x05, false, 0x01),
+        ];
+        let rom = banked_rom();
+        for (addr, val, ram_enabled, bank) in cases {
+            let mut mbc = Mbc2::new();
+            let mut ram = vec![0; MBC2_RAM_SIZE];
+            mbc.write_rom(addr, val);
+            mbc.write_ram(&mut ram, 0xA000, 0x05);
+            assert_eq!(mbc.read_ram(&ram, 0xA000) == 0xF5, ram_enabled, "{:#04x} to {:#06x}", val, addr);
+            assert_eq!(mbc.read_rom(&rom, 0x4000), bank, "{:#04x} to {:#06x}", val, addr);
+            assert_eq!(mbc.read_rom(&rom, 0x0000), 0x00, "{:#04x} to {:#06x}", val, addr);
+        }
+    }
+
+    #[test]
+    fn ram_half_bytes_mirrored() {
+        let mut mbc = Mbc2::new();
+        let mut ram = vec![0; MBC2_RAM_SIZE];
+        mbc.write_rom(0x0000, 0x0A);
+        // (address written, value, addresses reading it back)
+        let cases: [(u16, u8, &[u16]); 3] = [
+            (0xA000, 0xA5, &[0xA000, 0xA200, 0xBE00]),
+            (0xA1FF, 0x3C, &[0xA1FF, 0xA3FF, 0xBFFF]),
+            (0xB123, 0x0F, &[0xA123, 0xB323]),
+        ];
+        for (addr, val, mirrors) in cases {
+            assert!(mbc.write_ram(&mut ram, addr, val));
+            // The upper nibble isn't stored
+            assert!(!mbc.write_ram(&mut ram, addr, val & 0x0F));
+            for &mirror in mirrors {
+                let read = mbc.read_ram(&ram, mirror);
+                assert_eq!(read, 0xF0 | val, "{:#04x} to {:#06x}, read {:#06x}", val, addr, mirror);
+            }
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use crate::cartridge::RAM_BANK_SIZE;
+use crate::mbc::clock::Clock;
+use crate::mbc::{ram_offset, rom_byte, store, Mbc, RtcClock};
+
+/// Size of the RTC trailer appended to the save RAM, the format used by VBA and BGB:
+/// the 5 registers then the 5 latched registers as little endian u32, then the unix timestamp
+/// of the save as a little endian u64.
+pub const RTC_SAVE_SIZE: usize = 48;
+
+const DH_DAY_HIGH: u8 = 0x01;
+const DH_HALT: u8 = 0x40;
+const DH_CARRY: u8 = 0x80;
+
+/// Real time clock of the MBC3, registers 0x08-0x0C: seconds, minutes, hours, lower 8 bits of
+/// the day counter and DH (bit 0: day counter bit 8, bit 6: halt, bit 7: day counter carry).
+#[derive(Clone, Debug)]
+struct Rtc {
+    registers: [u8; 5],
+    latched: [u8; 5],
+    clock: Clock,
+}
+
+impl Rtc {
+    fn new() -> Self {
+        Rtc {
+            registers: [0; 5],
+            latched: [0; 5],
+            clock: Clock::new(),
+        }
+    }
+
+    fn halted(&self) -> bool {
+        self.registers[4] & DH_HALT != 0
+    }
+
+    fn tick(&mut self, cycles: u16) {
+        if !self.halted() {
+            let seconds = self.clock.tick(cycles);
+            self.advance(seconds);
+        }
+    }
+
+    /// Catch up with the host clock.
+    fn sync(&mut self) {
+        let seconds = self.clock.sync();
+        if !self.halted() {
+            self.advance(seconds);
+        }
+    }
+
+    fn set_source(&mut self, source: RtcClock) {
+        self.sync();
+        self.clock.set_source(source);
+    }
+
+    fn advance(&mut self, mut seconds: u64) {
+        // Registers written with out of range values count up to their bit width before
+        // wrapping to 0, step one second at a time until they are back in range
+        while seconds > 0 && !self.in_range() {
+            self.advance_one_second();
+            seconds -= 1;
+        }
+        if seconds == 0 {
+            return;
+        }
+        let [s, m, h, _, _] = self.registers;
+        let total = s as u64 + 60 * (m as u64 + 60 * (h as u64 + 24 * self.days() as u64)) + seconds;
+        self.registers[0] = (total % 60) as u8;
+        self.registers[1] = (total / 60 % 60) as u8;
+        self.registers[2] = (total / 3600 % 24) as u8;
+        let days = total / 86400;
+        if days > 0x1FF {
+            self.registers[4] |= DH_CARRY;
+        }
+        self.set_days((days & 0x1FF) as u16);
+    }
+
+    fn advance_one_second(&mut self) {
+        let [s, m, h, _, _] = &mut self.registers;
+        *s = (*s + 1) & 0x3F;
+        if *s != 60 {
+            return;
+        }
+        *s = 0;
+        *m = (*m + 1) & 0x3F;
+        if *m != 60 {
+            return;
+        }
+        *m = 0;
+        *h = (*h + 1) & 0x1F;
+        if *h != 24 {
+            return;
+        }
+        *h = 0;
+        let days = self.days() + 1;
+        if days > 0x1FF {
+            self.registers[4] |= DH_CARRY;
+        }
+        self.set_days(days & 0x1FF);
+    }
+
+    fn in_range(&self) -> bool {
+        self.registers[0] < 60 && self.registers[1] < 60 && self.registers[2] < 24
+    }
+
+    fn days(&self) -> u16 {
+        u16::from_le_bytes([self.registers[3], self.registers[4] & DH_DAY_HIGH])
+    }
+
+    fn set_days(&mut self, days: u16) {
+        let [low, high] = days.to_le_bytes();
+        self.registers[3] = low;
+        self.registers[4] = (self.registers[4] & !DH_DAY_HIGH) | (high & DH_DAY_HIGH);
+    }
+
+    fn latch(&mut self) {
+        self.sync();
+        self.latched = self.registers;
+    }
+
+    /// Returns whether the register changed.
+    fn write(&mut self, register: usize, val: u8) -> bool {
+        self.sync();
+        const MASKS: [u8; 5] = [0x3F, 0x3F, 0x1F, 0xFF, DH_DAY_HIGH | DH_HALT | DH_CARRY];
+        if register == 0 {
+            // Writing the seconds resets the sub-second divider
+            self.clock.reset_sub_second();
+        }
+        store(&mut self.registers[register], val & MASKS[register])
+    }
+
+    fn save(&self) -> [u8; RTC_SAVE_SIZE] {
+        let mut data = [0; RTC_SAVE_SIZE];
+        for (i, val) in self.registers.iter().chain(self.latched.iter()).enumerate() {
+            data[i * 4..i * 4 + 4].copy_from_slice(&(*val as u32).to_le_bytes());
+        }
+        data[40..48].copy_from_slice(&self.clock.timestamp().to_le_bytes());
+        data
+    }
+
+    fn load(&mut self, data: &[u8]) {
+        let mut values = data.chunks_exact(4).map(|c| c[0]);
+        for val in self.registers.iter_mut().chain(self.latched.iter_mut()) {
+            *val = values.next().unwrap_or(0);
+        }
+        // Some emulators write a 32 bits timestamp (44 bytes trailer)
+        let mut timestamp = [0; 8];
+        let end = data.len().min(48);
+        if end > 40 {
+            timestamp[..end - 40].copy_from_slice(&data[40..end]);
+        }
+        self.clock.set_timestamp(u64::from_le_bytes(timestamp));
+        // The time spent while the emulator was closed
+        self.sync();
+    }
+}
+
+/// MBC3, up to 2 MiB of ROM, 32 KiB of RAM and an optional real time clock.
+/// The MBC30 variant has a 8 bits ROM bank register and 8 RAM banks (4 MiB / 64 KiB).
+pub struct Mbc3 {
+    ram_and_timer_enabled: bool,
+    rom_bank: u8,
+    /// RAM bank 0x00-0x07 or RTC register 0x08-0x0C
+    ram_bank: u8,
+    /// Last value written to the latch register, the registers are latched by 0x00 then 0x01
+    latch: u8,
+    mbc30: bool,
+    rtc: Option<Rtc>,
+}
+
+impl Mbc3 {
+    pub fn new(rom: &[u8], ram_size: usize, timer: bool) -> Self {
+        Mbc3 {
+            ram_and_timer_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            latch: 0xFF,
+            mbc30: rom.len() > 0x20_0000 || ram_size > 4 * RAM_BANK_SIZE,
+            rtc: if timer { Some(Rtc::new()) } else { None },
+        }
+    }
+
+    fn rtc_register(&self) -> Option<usize> {
+        match self.ram_bank {
+            0x08..=0x0C if self.rtc.is_some() => Some(self.ram_bank as usize - 0x08),
+            _ => None,
+        }
+    }
+}
+
+impl Mbc for Mbc3 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        rom_byte(rom, bank, addr)
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        match addr {
+            0x0000..=0x1FFF => self.ram_and_timer_enabled = val & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                self.rom_bank = if self.mbc30 { val } else { val & 0x7F };
+                if self.rom_bank == 0 {
+                    self.rom_bank = 1;
+                }
+            }
+            0x4000..=0x5FFF => self.ram_bank = val & 0x0F,
+            _ => {
+                if self.latch == 0x00 && val == 0x01 {
+                    if let Some(rtc) = self.rtc.as_mut() {
+                        rtc.latch();
+                    }
+                }
+                self.latch = val;
+            }
+        }
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_and_timer_enabled {
+            return 0xFF;
+        }
+        if let Some(register) = self.rtc_register() {
+            return self.rtc.as_ref().map_or(0xFF, |rtc| rtc.latched[register]);
+        }
+        let max_bank = if self.mbc30 { 0x07 } else { 0x03 };
+        match ram_offset(ram, self.ram_bank as usize, addr) {
+            Some(offset) if self.ram_bank <= max_bank => ram[offset],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if !self.ram_and_timer_enabled {
+            return false;
+        }
+        if let Some(register) = self.rtc_register() {
+            return self.rtc.as_mut().is_some_and(|rtc| rtc.write(register, val));
+        }
+        let max_bank = if self.mbc30 { 0x07 } else { 0x03 };
+        match ram_offset(ram, self.ram_bank as usize, addr) {
+            Some(offset) if self.ram_bank <= max_bank => store(&mut ram[offset], val),
+            _ => false,
+        }
+    }
+
+    fn tick(&mut self, cycles: u16) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.tick(cycles);
+        }
+    }
+
+    fn set_rtc_clock(&mut self, clock: RtcClock) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.set_source(clock);
+        }
+    }
+
+    fn save_extra(&self) -> Option<Vec<u8>> {
+        self.rtc.as_ref().map(|rtc| rtc.save().to_vec())
+    }
+
+    fn load_extra(&mut self, data: &[u8]) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.load(data);
+        }
+    }
+
+    fn extra_sizes(&self) -> &'static [usize] {
+        // Some emulators write a 32 bits timestamp
+        match self.rtc {
+            Some(_) => &[RTC_SAVE_SIZE, RTC_SAVE_SIZE - 4],
+            None => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seconds counted by the clock registers.
+    fn total_seconds(rtc: &Rtc) -> u64 {
+        let [s, m, h, _, _] = rtc.registers;
+        s as u64 + 60 * (m as u64 + 60 * (h as u64 + 24 * rtc.days() as u64))
+    }
+
+    #[test]
+    fn save_keeps_time_since_last_sync() {
+        let mut mbc = Mbc3::new(&[0; 0x8000], 0x2000, true);
+        // The last latch was 100 seconds ago
+        let rtc = mbc.rtc.as_mut().unwrap();
+        let timestamp = rtc.clock.timestamp();
+        rtc.clock.set_timestamp(timestamp - 100);
+
+        let save = mbc.save_extra().unwrap();
+        let mut loaded = Mbc3::new(&[0; 0x8000], 0x2000, true);
+        loaded.load_extra(&save);
+        let seconds = total_seconds(loaded.rtc.as_ref().unwrap());
+        assert!((100..=102).contains(&seconds), "{} seconds", seconds);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::mbc::{ram_offset, rom_byte, store, Mbc};
+
+/// MBC5, up to 8 MiB of ROM and 128 KiB of RAM.
+///
+/// The ROM bank is 9 bits wide and bank 0 can be mapped at 0x4000-0x7FFF. On the rumble
+/// cartridges bit 3 of the RAM bank register drives the motor instead of selecting a bank.
+pub struct Mbc5 {
+    ram_enabled: bool,
+    rom_bank: u16,
+    ram_bank: u8,
+    has_rumble: bool,
+    rumble: bool,
+}
+
+impl Mbc5 {
+    pub fn new(has_rumble: bool) -> Self {
+        Mbc5 {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            rumble: false,
+        }
+    }
+}
+
+impl Mbc for Mbc5 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        rom_byte(rom, bank, addr)
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        match addr {
+            // Unlike the other controllers only 0x0A enables the RAM
+            0x0000..=0x1FFF => self.ram_enabled = val == 0x0A,
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | val as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((val as u16 & 1) << 8),
+            0x4000..=0x5FFF => {
+                if self.has_rumble {
+                    self.rumble = val & 0x08 != 0;
+                    self.ram_bank = val & 0x07;
+                } else {
+                    self.ram_bank = val & 0x0F;
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        match ram_offset(ram, self.ram_bank as usize, addr) {
+            Some(offset) if self.ram_enabled => ram[offset],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        match ram_offset(ram, self.ram_bank as usize, addr) {
+            Some(offset) if self.ram_enabled => store(&mut ram[offset], val),
+            _ => false,
+        }
+    }
+
+    fn rumble(&self) -> bool {
+        self.rumble
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+    /// ROM of 512 banks starting with their number as a little endian u16.
+    fn banked_rom() -> Vec<u8> {
+        let mut rom = vec![0; 512 * ROM_BANK_SIZE];
+        for bank in 0..512 {
+            rom[bank * ROM_BANK_SIZE..bank * ROM_BANK_SIZE + 2].copy_from_slice(&(bank as u16).to_le_bytes());
+        }
+        rom
+    }
+
+    #[test]
+    fn rom_banking() {
+        // (register writes, bank at 0x4000)
+        let cases: [(&[(u16, u8)], u16); 8] = [
+            (&[], 0x001),
+            // The bank 0 can be mapped at 0x4000
+            (&[(0x2000, 0x00)], 0x000),
+            (&[(0x2FFF, 0x42)], 0x042),
+            (&[(0x3000, 0x01)], 0x101),
+            (&[(0x2000, 0xFF), (0x3FFF, 0x01)], 0x1FF),
+            (&[(0x3000, 0x01), (0x2000, 0x00)], 0x100),
+            (&[(0x3000, 0x01), (0x3000, 0x00)], 0x001),
+            // Only bit 0 of the upper register is connected
+            (&[(0x3000, 0xFE)], 0x001),
+        ];
+        let rom = banked_rom();
+        for (writes, bank) in cases {
+            let mut mbc = Mbc5::new(false);
+            for &(addr, val) in writes {
+                mbc.write_rom(addr, val);
+            }
+            let read = u16::from_le_bytes([mbc.read_rom(&rom, 0x4000), mbc.read_rom(&rom, 0x4001)]);
+            assert_eq!(read, bank, "{:x?}", writes);
+            assert_eq!(mbc.read_rom(&rom, 0x0000), 0x00, "{:x?}", writes);
+        }
+    }
+
+    #[test]
+    fn ram_bank_and_rumble() {
+        // (rumble cartridge, RAM bank register, RAM bank selected, motor on)
+        let cases = [
+            (false, 0x03, 3, false),
+            (false, 0x0B, 11, false),
+            (false, 0x0F, 15, false),
+            (true, 0x03, 3, false),
+            // The bit 3 drives the motor
+            (true, 0x0B, 3, true),
+            (true, 0x08, 0, true),
+            (true, 0x0F, 7, true),
+        ];
+        for (has_rumble, val, bank, rumble) in cases {
+            let mut mbc = Mbc5::new(has_rumble);
+            let mut ram = vec![0; 16 * RAM_BANK_SIZE];
+            mbc.write_rom(0x0000, 0x0A);
+            mbc.write_rom(0x4000, val);
+            assert!(mbc.write_ram(&mut ram, 0xA000, 0x42));
+            assert_eq!(ram[bank * RAM_BANK_SIZE], 0x42, "rumble cartridge {}, {:#04x}", has_rumble, val);
+            assert_eq!(mbc.read_ram(&ram, 0xA000), 0x42, "rumble cartridge {}, {:#04x}", has_rumble, val);
+            assert_eq!(mbc.rumble(), rumble, "rumble cartridge {}, {:#04x}", has_rumble, val);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use log::*;
+
+use crate::mbc::{store, Mbc};
+
+/// Size of the flash of the MBC6 cartridges, stored after the RAM in the saves.
+pub const MBC6_FLASH_SIZE: usize = 0x10_0000;
+/// Size of the MBC6 RAM, banked by 4 KiB.
+pub const MBC6_RAM_SIZE: usize = 0x8000;
+
+const BANK_SIZE: usize = 0x2000;
+const RAM_BANK_SIZE: usize = 0x1000;
+const FLASH_SECTOR_SIZE: usize = 0x1_0000;
+/// Manufacturer and device id of the Macronix MX29F008 flash.
+const FLASH_ID: [u8; 2] = [0xC2, 0x81];
+
+/// Command sequence of the flash chip, every command starts by writing 0xAA at 0x5555 then 0x55
+/// at 0x2AAA.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FlashState {
+    Read,
+    Unlock1,
+    Unlock2,
+    /// The next write programs a byte
+    Program,
+    EraseUnlock,
+    EraseUnlock1,
+    EraseUnlock2,
+    /// Reads return the chip id until reset
+    Id,
+}
+
+/// MBC6, 1 MiB of ROM, 32 KiB of RAM and 1 MiB of flash, only used by Net de Get.
+///
+/// 0x4000-0x5FFF and 0x6000-0x7FFF are 2 independent 8 KiB windows mapping either ROM or flash,
+/// 0xA000-0xAFFF and 0xB000-0xBFFF are 2 independent 4 KiB RAM windows.
+pub struct Mbc6 {
+    ram_enabled: bool,
+    ram_banks: [u8; 2],
+    flash_enabled: bool,
+    flash_write_enabled: bool,
+    rom_banks: [u8; 2],
+    /// The window maps the flash instead of the ROM
+    flash_mapped: [bool; 2],
+    flash: Vec<u8>,
+    flash_state: FlashState,
+}
+
+impl Mbc6 {
+    pub fn new() -> Self {
+        Mbc6 {
+            ram_enabled: false,
+            ram_banks: [0; 2],
+            flash_enabled: false,
+            flash_write_enabled: false,
+            rom_banks: [0; 2],
+            flash_mapped: [false; 2],
+            flash: vec![0xFF; MBC6_FLASH_SIZE],
+            flash_state: FlashState::Read,
+        }
+    }
+
+    /// Window of 0x4000-0x7FFF the address falls in.
+    fn window(addr: u16) -> usize {
+        (addr as usize - 0x4000) / BANK_SIZE
+    }
+
+    fn flash_offset(&self, addr: u16) -> usize {
+        let bank = self.rom_banks[Self::window(addr)] as usize;
+        (bank * BANK_SIZE + (addr as usize & (BANK_SIZE - 1))) % MBC6_FLASH_SIZE
+    }
+
+    fn ram_offset(&self, ram: &[u8], addr: u16) -> Option<usize> {
+        if ram.is_empty() {
+            return None;
+        }
+        let bank = self.ram_banks[(addr as usize - 0xA000) / RAM_BANK_SIZE] as usize;
+        Some((bank * RAM_BANK_SIZE + (addr as usize & (RAM_BANK_SIZE - 1))) % ram.len())
+    }
+
+    /// Returns whether the content of the flash changed.
+    fn write_flash(&mut self, offset: usize, val: u8) -> bool {
+        use FlashState::*;
+        let chip_addr = offset & 0xFFFF;
+        let mut changed = false;
+        self.flash_state = match (self.flash_state, chip_addr, val) {
+            (_, _, 0xF0) => Read,
+            (Read, 0x5555, 0xAA) => Unlock1,
+            (Unlock1, 0x2AAA, 0x55) => Unlock2,
+            (Unlock2, 0x5555, 0xA0) => Program,
+            (Unlock2, 0x5555, 0x80) => EraseUnlock,
+            (Unlock2, 0x5555, 0x90) => Id,
+            (Program, _, _) => {
+                // Programming can only clear bits
+                let programmed = self.flash[offset] & val;
+                changed = store(&mut self.flash[offset], programmed);
+                Read
+            }
+            (EraseUnlock, 0x5555, 0xAA) => EraseUnlock1,
+            (EraseUnlock1, 0x2AAA, 0x55) => EraseUnlock2,
+            (EraseUnlock2, _, 0x30) => {
+                let sector = &mut self.flash[offset & !(FLASH_SECTOR_SIZE - 1)..][..FLASH_SECTOR_SIZE];
+                changed = sector.iter().any(|&byte| byte != 0xFF);
+                sector.fill(0xFF);
+                Read
+            }
+            (EraseUnlock2, 0x5555, 0x10) => {
+                changed = self.flash.iter().any(|&byte| byte != 0xFF);
+                self.flash.fill(0xFF);
+                Read
+            }
+            (Id, _, _) => Id,
+            (state, _, _) => {
+                debug!("Unexpected flash write {:#04x} at {:#07x} in {:?}", val, offset, state);
+                Read
+            }
+        };
+        changed
+    }
+}
+
+impl Default for Mbc6 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc for Mbc6 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            return rom.get(addr as usize).copied().unwrap_or(0xFF);
+        }
+        let window = Self::window(addr);
+        if !self.flash_mapped[window] {
+            let banks = (rom.len() / BANK_SIZE).max(1);
+            let bank = self.rom_banks[window] as usize % banks;
+            return rom.get(bank * BANK_SIZE + (addr as usize & (BANK_SIZE - 1))).copied().unwrap_or(0xFF);
+        }
+        if !self.flash_enabled {
+            return 0xFF;
+        }
+        let offset = self.flash_offset(addr);
+        match self.flash_state {
+            FlashState::Id => FLASH_ID[offset & 1],
+            _ => self.flash[offset],
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        match addr {
+            0x0000..=0x03FF => self.ram_enabled = val & 0x0F == 0x0A,
+            0x0400..=0x07FF => self.ram_banks[0] = val & 0x07,
+            0x0800..=0x0BFF => self.ram_banks[1] = val & 0x07,
+            0x0C00..=0x0FFF => self.flash_enabled = val & 0x01 != 0,
+            0x1000 => self.flash_write_enabled = val & 0x01 != 0,
+            0x2000..=0x27FF => self.rom_banks[0] = val & 0x7F,
+            0x2800..=0x2FFF => self.flash_mapped[0] = val == 0x08,
+            0x3000..=0x37FF => self.rom_banks[1] = val & 0x7F,
+            0x3800..=0x3FFF => self.flash_mapped[1] = val == 0x08,
+            0x4000..=0x7FFF
+                if self.flash_mapped[Self::window(addr)] && self.flash_enabled && self.flash_write_enabled =>
+            {
+                let offset = self.flash_offset(addr);
+                return self.write_flash(offset, val);
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        match self.ram_offset(ram, addr) {
+            Some(offset) if self.ram_enabled => ram[offset],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        match self.ram_offset(ram, addr) {
+            Some(offset) if self.ram_enabled => store(&mut ram[offset], val),
+            _ => false,
+        }
+    }
+
+    fn save_extra(&self) -> Option<Vec<u8>> {
+        Some(self.flash.clone())
+    }
+
+    fn load_extra(&mut self, data: &[u8]) {
+        let size = data.len().min(MBC6_FLASH_SIZE);
+        self.flash[..size].copy_from_slice(&data[..size]);
+    }
+
+    fn extra_sizes(&self) -> &'static [usize] {
+        &[MBC6_FLASH_SIZE]
+    }
+}
@@ -0,0 +1,324 @@
+use crate::input::Tilt;
+use crate::mbc::{rom_byte, Mbc};
+
+/// Size of the 93LC56 EEPROM of the MBC7 cartridges, stored as the RAM in the saves.
+pub const MBC7_EEPROM_SIZE: usize = 0x100;
+
+/// Accelerometer value at rest.
+const ACCELEROMETER_CENTER: f32 = 0x81D0 as f32;
+/// Accelerometer delta for 1 g.
+const ACCELEROMETER_G: f32 = 0x70 as f32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EepromState {
+    /// Waiting for the start bit
+    Idle,
+    /// Receiving the 2 bits opcode and the 8 bits address
+    Command,
+    /// Shifting out the word read
+    Read,
+    /// Receiving the word to write at the address
+    Write,
+    /// Receiving the word to write everywhere
+    WriteAll,
+}
+
+/// 93LC56 serial EEPROM, 128 words of 16 bits driven bit by bit by the game: each rising edge of
+/// CLK while CS is high shifts DI in or DO out.
+struct Eeprom {
+    cs: bool,
+    clk: bool,
+    di: bool,
+    /// DO, high when ready
+    output: bool,
+    state: EepromState,
+    shift: u16,
+    bits: u8,
+    address: u8,
+    write_enabled: bool,
+}
+
+impl Eeprom {
+    fn new() -> Self {
+        Eeprom {
+            cs: false,
+            clk: false,
+            di: false,
+            output: true,
+            state: EepromState::Idle,
+            shift: 0,
+            bits: 0,
+            address: 0,
+            write_enabled: false,
+        }
+    }
+
+    fn read(&self) -> u8 {
+        (self.cs as u8) << 7 | (self.clk as u8) << 6 | (self.di as u8) << 1 | self.output as u8
+    }
+
+    /// Drive CS, CLK and DI, returns whether the content of the EEPROM changed.
+    fn write(&mut self, eeprom: &mut [u8], val: u8) -> bool {
+        let (cs, clk, di) = (val & 0x80 != 0, val & 0x40 != 0, val & 0x02 != 0);
+        let rising = cs && !self.clk && clk;
+        self.cs = cs;
+        self.clk = clk;
+        self.di = di;
+        if !cs {
+            self.state = EepromState::Idle;
+        } else if rising {
+            return self.clock(eeprom);
+        }
+        false
+    }
+
+    fn clock(&mut self, eeprom: &mut [u8]) -> bool {
+        let mut changed = false;
+        match self.state {
+            EepromState::Idle => {
+                if self.di {
+                    self.state = EepromState::Command;
+                    self.shift = 0;
+                    self.bits = 0;
+                }
+            }
+            EepromState::Command => {
+                self.shift = self.shift << 1 | self.di as u16;
+                self.bits += 1;
+                if self.bits == 10 {
+                    changed = self.execute(eeprom);
+                }
+            }
+            EepromState::Read => {
+                self.output = self.shift & 0x8000 != 0;
+                self.shift <<= 1;
+                self.bits += 1;
+                if self.bits == 16 {
+                    self.state = EepromState::Idle;
+                }
+            }
+            EepromState::Write | EepromState::WriteAll => {
+                self.shift = self.shift << 1 | self.di as u16;
+                self.bits += 1;
+                if self.bits == 16 {
+                    if self.write_enabled {
+                        changed = if self.state == EepromState::Write {
+                            Self::set_word(eeprom, self.address, self.shift)
+                        } else {
+                            (0..0x80).fold(false, |changed, address| Self::set_word(eeprom, address, self.shift) | changed)
+                        };
+                    }
+                    self.output = true;
+                    self.state = EepromState::Idle;
+                }
+            }
+        }
+        changed
+    }
+
+    fn execute(&mut self, eeprom: &mut [u8]) -> bool {
+        let mut changed = false;
+        let address = (self.shift & 0x7F) as u8;
+        self.state = EepromState::Idle;
+        self.bits = 0;
+        match self.shift >> 8 {
+            // READ, a dummy 0 is output before the word
+            0b10 => {
+                self.shift = Self::word(eeprom, address);
+                self.output = false;
+                self.state = EepromState::Read;
+            }
+            // WRITE
+            0b01 => {
+                self.address = address;
+                self.shift = 0;
+                self.state = EepromState::Write;
+            }
+            // ERASE
+            0b11 => {
+                if self.write_enabled {
+                    changed = Self::set_word(eeprom, address, 0xFFFF);
+                }
+                self.output = true;
+            }
+            _ => match (self.shift >> 6) & 0x03 {
+                0b00 => self.write_enabled = false,
+                0b01 => {
+                    self.shift = 0;
+                    self.state = EepromState::WriteAll;
+                }
+                0b10 => {
+                    if self.write_enabled {
+                        changed = eeprom.iter().any(|&byte| byte != 0xFF);
+                        eeprom.fill(0xFF);
+                    }
+                    self.output = true;
+                }
+                _ => self.write_enabled = true,
+            },
+        }
+        changed
+    }
+
+    fn word(eeprom: &[u8], address: u8) -> u16 {
+        let offset = address as usize * 2;
+        match eeprom.get(offset..offset + 2) {
+            Some(word) => u16::from_le_bytes([word[0], word[1]]),
+            None => 0xFFFF,
+        }
+    }
+
+    /// Returns whether the word changed.
+    fn set_word(eeprom: &mut [u8], address: u8, val: u16) -> bool {
+        let changed = Self::word(eeprom, address) != val;
+        let offset = address as usize * 2;
+        if let Some(word) = eeprom.get_mut(offset..offset + 2) {
+            word.copy_from_slice(&val.to_le_bytes());
+        }
+        changed
+    }
+}
+
+/// MBC7, up to 2 MiB of ROM, a 2 axis accelerometer and a 256 bytes EEPROM, used by the tilt
+/// games. The cartridge RAM holds the EEPROM.
+///
+/// 0xA000-0xAFFF maps registers selected by the address bits 4-7 once enabled by writing 0x0A to
+/// 0x0000-0x1FFF and 0x40 to 0x4000-0x5FFF: writing 0x55 to Ax0x then 0xAA to Ax1x latches the
+/// accelerometer, Ax2x-Ax5x read the latched X and Y, Ax8x drives the EEPROM.
+pub struct Mbc7 {
+    ram_enabled: bool,
+    ram_enabled2: bool,
+    rom_bank: u8,
+    tilt: Tilt,
+    /// The latch was erased by 0x55, the next 0xAA latches
+    latch_erased: bool,
+    x: u16,
+    y: u16,
+    eeprom: Eeprom,
+}
+
+impl Mbc7 {
+    pub fn new() -> Self {
+        Mbc7 {
+            ram_enabled: false,
+            ram_enabled2: false,
+            rom_bank: 1,
+            tilt: Tilt::default(),
+            latch_erased: false,
+            x: 0x8000,
+            y: 0x8000,
+            eeprom: Eeprom::new(),
+        }
+    }
+
+    fn axis(g: f32) -> u16 {
+        (ACCELEROMETER_CENTER + ACCELEROMETER_G * g).clamp(0.0, u16::MAX as f32) as u16
+    }
+}
+
+impl Default for Mbc7 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc for Mbc7 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        rom_byte(rom, bank, addr)
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = val & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = val & 0x7F,
+            0x4000..=0x5FFF => self.ram_enabled2 = val == 0x40,
+            _ => {}
+        }
+        false
+    }
+
+    fn read_ram(&self, _ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled || !self.ram_enabled2 || addr >= 0xB000 {
+            return 0xFF;
+        }
+        match (addr >> 4) & 0x0F {
+            0x2 => self.x as u8,
+            0x3 => (self.x >> 8) as u8,
+            0x4 => self.y as u8,
+            0x5 => (self.y >> 8) as u8,
+            0x6 => 0x00,
+            0x8 => self.eeprom.read(),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if !self.ram_enabled || !self.ram_enabled2 || addr >= 0xB000 {
+            return false;
+        }
+        match (addr >> 4) & 0x0F {
+            0x0 if val == 0x55 => {
+                self.latch_erased = true;
+                self.x = 0x8000;
+                self.y = 0x8000;
+            }
+            0x1 if val == 0xAA && self.latch_erased => {
+                self.latch_erased = false;
+                self.x = Self::axis(self.tilt.x);
+                self.y = Self::axis(self.tilt.y);
+            }
+            0x8 => return self.eeprom.write(ram, val),
+            _ => {}
+        }
+        false
+    }
+
+    fn set_tilt(&mut self, tilt: Tilt) {
+        self.tilt = tilt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CS: u8 = 0x80;
+    const CLK: u8 = 0x40;
+    const DI: u8 = 0x02;
+
+    /// Shift `bits` into the EEPROM MSB first, returns whether the content changed.
+    fn send(mbc: &mut Mbc7, eeprom: &mut [u8], bits: &[u8]) -> bool {
+        bits.iter().fold(false, |changed, &bit| {
+            let di = if bit != 0 { DI } else { 0 };
+            mbc.write_ram(eeprom, 0xA080, CS | di);
+            mbc.write_ram(eeprom, 0xA080, CS | CLK | di) | changed
+        })
+    }
+
+    #[test]
+    fn only_eeprom_writes_modify_the_save() {
+        let mut mbc = Mbc7::new();
+        let mut eeprom = [0xFF; MBC7_EEPROM_SIZE];
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x4000, 0x40);
+
+        // Accelerometer latch, done every frame
+        assert!(!mbc.write_ram(&mut eeprom, 0xA000, 0x55));
+        assert!(!mbc.write_ram(&mut eeprom, 0xA010, 0xAA));
+
+        // EWEN then WRITE 0x1234 at word 3
+        assert!(!send(&mut mbc, &mut eeprom, &[1, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0]));
+        mbc.write_ram(&mut eeprom, 0xA080, 0);
+        let write = [1, 0, 1, 0, 0, 0, 0, 0, 0, 1, 1];
+        let word = [0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 1, 1, 0, 1, 0, 0];
+        assert!(!send(&mut mbc, &mut eeprom, &write));
+        assert!(send(&mut mbc, &mut eeprom, &word));
+        assert_eq!(eeprom[6..8], [0x34, 0x12]);
+
+        // Writing the same word again changes nothing
+        mbc.write_ram(&mut eeprom, 0xA080, 0);
+        send(&mut mbc, &mut eeprom, &write);
+        assert!(!send(&mut mbc, &mut eeprom, &word));
+    }
+}
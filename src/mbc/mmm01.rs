@@ -0,0 +1,138 @@
+use crate::mbc::{ram_offset, rom_byte, store, Mbc};
+
+/// MMM01, the controller of the multicarts, up to 8 MiB of ROM and 128 KiB of RAM.
+///
+/// At power on the last 32 KiB of the ROM, the game menu, are mapped and every register is
+/// writable. The menu configures the bank of the game and the masks of the bank bits the game
+/// can't change, then sets bit 6 of 0x0000-0x1FFF: from then on the controller behaves like a
+/// MBC1 restricted to the banks of the game.
+pub struct Mmm01 {
+    ram_enabled: bool,
+    mapped: bool,
+    /// Bits 0-4 of the ROM bank
+    rom_low: u8,
+    /// Bits 5-6 of the ROM bank
+    rom_mid: u8,
+    /// Bits 7-8 of the ROM bank
+    rom_high: u8,
+    /// Bits of `rom_low` the game can't change, bits 1-4 only
+    rom_mask: u8,
+    ram_low: u8,
+    ram_high: u8,
+    /// Bits of `ram_low` the game can't change
+    ram_mask: u8,
+    mode: bool,
+    mode_locked: bool,
+    /// In mode 1 the ROM bank bits 5-6 and the RAM bank bits 0-1 are swapped
+    multiplex: bool,
+}
+
+impl Mmm01 {
+    pub fn new() -> Self {
+        Mmm01 {
+            ram_enabled: false,
+            mapped: false,
+            rom_low: 0,
+            rom_mid: 0,
+            rom_high: 0,
+            rom_mask: 0,
+            ram_low: 0,
+            ram_high: 0,
+            ram_mask: 0,
+            mode: false,
+            mode_locked: false,
+            multiplex: false,
+        }
+    }
+
+    fn rom_bank(&self, addr: u16) -> usize {
+        if !self.mapped {
+            // The last 2 banks of the 8 MiB address space, wrapped to the size of the ROM
+            return if addr < 0x4000 { 0x1FE } else { 0x1FF };
+        }
+        let low = if addr < 0x4000 {
+            self.rom_low & self.rom_mask
+        } else if self.rom_low & !self.rom_mask == 0 {
+            // Bank 0 of the game is mapped as 1, like the MBC1
+            self.rom_low | 1
+        } else {
+            self.rom_low
+        };
+        let mid = if self.multiplex && self.mode { self.ram_low } else { self.rom_mid };
+        (self.rom_high as usize) << 7 | (mid as usize) << 5 | low as usize
+    }
+
+    fn ram_bank(&self) -> usize {
+        let low = match (self.multiplex, self.mode) {
+            (true, true) => self.rom_mid,
+            (true, false) => 0,
+            _ => self.ram_low,
+        };
+        (self.ram_high as usize) << 2 | low as usize
+    }
+}
+
+impl Default for Mmm01 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc for Mmm01 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        rom_byte(rom, self.rom_bank(addr), addr)
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool {
+        match (addr, self.mapped) {
+            (0x0000..=0x1FFF, _) => {
+                self.ram_enabled = val & 0x0F == 0x0A;
+                if !self.mapped {
+                    self.ram_mask = (val >> 4) & 0x03;
+                    self.mapped = val & 0x40 != 0;
+                }
+            }
+            (0x2000..=0x3FFF, false) => {
+                self.rom_low = val & 0x1F;
+                self.rom_mid = (val >> 5) & 0x03;
+            }
+            (0x2000..=0x3FFF, true) => {
+                self.rom_low = (self.rom_low & self.rom_mask) | (val & 0x1F & !self.rom_mask);
+            }
+            (0x4000..=0x5FFF, false) => {
+                self.ram_low = val & 0x03;
+                self.ram_high = (val >> 2) & 0x03;
+                self.rom_high = (val >> 4) & 0x03;
+                self.mode_locked = val & 0x40 != 0;
+            }
+            (0x4000..=0x5FFF, true) => {
+                self.ram_low = (self.ram_low & self.ram_mask) | (val & 0x03 & !self.ram_mask);
+            }
+            (_, mapped) => {
+                if !self.mode_locked {
+                    self.mode = val & 0x01 != 0;
+                }
+                if !mapped {
+                    // Bits 2-5 mask the ROM bank bits 1-4
+                    self.rom_mask = (val >> 1) & 0x1E;
+                    self.multiplex = val & 0x40 != 0;
+                }
+            }
+        }
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        match ram_offset(ram, self.ram_bank(), addr) {
+            Some(offset) if self.ram_enabled => ram[offset],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        match ram_offset(ram, self.ram_bank(), addr) {
+            Some(offset) if self.ram_enabled => store(&mut ram[offset], val),
+            _ => false,
+        }
+    }
+}
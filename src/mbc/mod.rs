@@ -0,0 +1,129 @@
+//! Memory bank controllers, they map the banks of the cartridge ROM and RAM in the address
+//! space and expose their registers through writes to the ROM area.
+
+mod camera;
+mod clock;
+mod huc1;
+mod huc3;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod mbc6;
+mod mbc7;
+mod mmm01;
+mod tama5;
+
+pub use camera::PocketCamera;
+pub use clock::RtcClock;
+pub use huc1::HuC1;
+pub use huc3::{HuC3, HUC3_RTC_SAVE_SIZE};
+pub use mbc1::Mbc1;
+pub use mbc2::{Mbc2, MBC2_RAM_SIZE};
+pub use mbc3::{Mbc3, RTC_SAVE_SIZE};
+pub use mbc5::Mbc5;
+pub use mbc6::{Mbc6, MBC6_FLASH_SIZE, MBC6_RAM_SIZE};
+pub use mbc7::{Mbc7, MBC7_EEPROM_SIZE};
+pub use mmm01::Mmm01;
+pub use tama5::{Tama5, TAMA5_RAM_SIZE};
+
+use crate::cartridge::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+use crate::input::{ImageSource, Tilt};
+
+/// A memory bank controller, the cartridge owns the ROM and RAM the controller maps.
+pub trait Mbc {
+    /// Read in 0x0000-0x7FFF.
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8;
+    /// Write in 0x0000-0x7FFF, sets the controller registers. Returns whether the battery backed
+    /// state changed.
+    fn write_rom(&mut self, addr: u16, val: u8) -> bool;
+    /// Read in 0xA000-0xBFFF.
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8;
+    /// Write in 0xA000-0xBFFF. Returns whether the battery backed state changed, not the case of
+    /// the writes to the registers mapped there.
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool;
+
+    /// Advance the clocked parts of the controller, `cycles` are at normal speed.
+    fn tick(&mut self, _cycles: u16) {}
+
+    /// Select what drives the real time clock, if the controller has one.
+    fn set_rtc_clock(&mut self, _clock: RtcClock) {}
+
+    /// Battery backed state of the controller besides the RAM (real time clock, flash), stored
+    /// after the RAM in the saves.
+    fn save_extra(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restore the state stored after the RAM in the saves.
+    fn load_extra(&mut self, _data: &[u8]) {}
+
+    /// Sizes of the state `load_extra` accepts, empty when the controller has none.
+    fn extra_sizes(&self) -> &'static [usize] {
+        &[]
+    }
+
+    /// State of the rumble motor, if the cartridge has one.
+    fn rumble(&self) -> bool {
+        false
+    }
+
+    /// Tilt read by the accelerometer, if the cartridge has one.
+    fn set_tilt(&mut self, _tilt: Tilt) {}
+
+    /// State of the infrared LED, if the cartridge has one.
+    fn ir_led(&self) -> bool {
+        false
+    }
+
+    /// Light received by the infrared sensor, if the cartridge has one.
+    fn set_ir_light(&mut self, _light: bool) {}
+
+    /// Source of the images of the camera, if the cartridge has one.
+    fn set_image_source(&mut self, _source: Box<dyn ImageSource>) {}
+}
+
+/// Cartridge without a controller, 32 KiB of ROM and optionally 8 KiB of RAM always mapped.
+pub struct RomOnly;
+
+impl Mbc for RomOnly {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        rom.get(addr as usize).copied().unwrap_or(0xFF)
+    }
+
+    fn write_rom(&mut self, _addr: u16, _val: u8) -> bool {
+        false
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        ram.get(addr as usize - 0xA000).copied().unwrap_or(0xFF)
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        ram.get_mut(addr as usize - 0xA000).is_some_and(|byte| store(byte, val))
+    }
+}
+
+/// Byte of the ROM at `addr` in `bank`, the bank number wraps around the ROM size like the
+/// unconnected upper bank lines do.
+#[inline]
+pub(crate) fn rom_byte(rom: &[u8], bank: usize, addr: u16) -> u8 {
+    let banks = (rom.len() / ROM_BANK_SIZE).max(1);
+    let offset = (bank % banks) * ROM_BANK_SIZE + (addr as usize & (ROM_BANK_SIZE - 1));
+    rom.get(offset).copied().unwrap_or(0xFF)
+}
+
+/// Store `val` in `byte`, returns whether the byte changed.
+#[inline]
+pub(crate) fn store(byte: &mut u8, val: u8) -> bool {
+    std::mem::replace(byte, val) != val
+}
+
+/// Offset in the RAM of `addr` in `bank`, None when the cartridge has no RAM.
+#[inline]
+pub(crate) fn ram_offset(ram: &[u8], bank: usize, addr: u16) -> Option<usize> {
+    if ram.is_empty() {
+        return None;
+    }
+    Some((bank * RAM_BANK_SIZE + (addr as usize & (RAM_BANK_SIZE - 1))) % ram.len())
+}
@@ -0,0 +1,94 @@
+//! Bandai TAMA5 controller. Only partially supported: the real time clock and alarm of its TAMA6
+//! chip are not emulated, so Tamagotchi 3 can't keep time.
+
+use log::*;
+
+use crate::mbc::{rom_byte, store, Mbc};
+
+/// Size of the TAMA5 internal memory, stored as the RAM in the saves.
+pub const TAMA5_RAM_SIZE: usize = 0x20;
+
+/// Bandai TAMA5, up to 512 KiB of ROM and 32 bytes of memory, used by the Tamagotchi game.
+///
+/// Every register is a nibble accessed through 0xA000-0xBFFF: the odd addresses select the
+/// register and the even addresses read or write it. Registers 0 and 1 hold the ROM bank, 4 and 5
+/// the byte to write, 6 the command and the address bit 4, and the write to 7, the address bits
+/// 0-3, executes the command. Registers 0xC and 0xD hold the byte read and 0xA reads 1 when ready.
+/// The TAMA6 clock chip is not emulated, its commands are ignored and the cartridge is reported
+/// without a timer.
+pub struct Tama5 {
+    register: u8,
+    rom_bank: u8,
+    value: u8,
+    command: u8,
+    address_high: u8,
+    read: u8,
+}
+
+impl Tama5 {
+    pub fn new() -> Self {
+        Tama5 { register: 0, rom_bank: 0, value: 0, command: 0, address_high: 0, read: 0 }
+    }
+
+    /// Returns whether the memory changed.
+    fn execute(&mut self, ram: &mut [u8], address_low: u8) -> bool {
+        let address = (self.address_high << 4 | address_low) as usize % TAMA5_RAM_SIZE;
+        match self.command {
+            0x0 => return ram.get_mut(address).is_some_and(|byte| store(byte, self.value)),
+            0x1 => self.read = ram.get(address).copied().unwrap_or(0xFF),
+            command => debug!("TAMA5 clock command {:#03x} at {:#04x} ignored", command, address),
+        }
+        false
+    }
+}
+
+impl Default for Tama5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc for Tama5 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        rom_byte(rom, bank, addr)
+    }
+
+    fn write_rom(&mut self, _addr: u16, _val: u8) -> bool {
+        false
+    }
+
+    fn read_ram(&self, _ram: &[u8], addr: u16) -> u8 {
+        if addr & 1 != 0 {
+            return 0xFF;
+        }
+        match self.register {
+            // Always ready
+            0xA => 0xF1,
+            0xC => 0xF0 | (self.read & 0x0F),
+            0xD => 0xF0 | (self.read >> 4),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        let val = val & 0x0F;
+        if addr & 1 != 0 {
+            self.register = val;
+            return false;
+        }
+        match self.register {
+            0x0 => self.rom_bank = (self.rom_bank & 0x10) | val,
+            0x1 => self.rom_bank = (self.rom_bank & 0x0F) | (val & 1) << 4,
+            0x4 => self.value = (self.value & 0xF0) | val,
+            0x5 => self.value = (self.value & 0x0F) | val << 4,
+            0x6 => {
+                self.address_high = val & 1;
+                self.command = val >> 1;
+            }
+            0x7 => return self.execute(ram, val),
+            register => debug!("TAMA5 write {:#03x} to register {:#03x} ignored", val, register),
+        }
+        false
+    }
+}
@@ -1,44 +1,145 @@
+use log::*;
+
+use crate::boot::{Model, BIOS};
+use crate::cartridge::Cartridge;
+use crate::dma::{OamDma, DMA_ADDR};
+use crate::error::EmulationError;
+use crate::interrupts::{Interrupt, IE_ADDR, IF_ADDR};
+use crate::ppu::{Ppu, BGP_ADDR, LCDC_ADDR, LYC_ADDR, WX_ADDR};
+use crate::timer::{Timer, DIV_ADDR, TAC_ADDR};
+
 pub type MMUAddress = u16;
 
-static BIOS: [u8;256] = [
-0x31, 0xFE, 0xFF, 0xAF, 0x21, 0xFF, 0x9F, 0x32, 0xCB, 0x7C, 0x20, 0xFB, 0x21, 0x26, 0xFF, 0x0E,
-0x11, 0x3E, 0x80, 0x32, 0xE2, 0x0C, 0x3E, 0xF3, 0xE2, 0x32, 0x3E, 0x77, 0x77, 0x3E, 0xFC, 0xE0,
-0x47, 0x11, 0x04, 0x01, 0x21, 0x10, 0x80, 0x1A, 0xCD, 0x95, 0x00, 0xCD, 0x96, 0x00, 0x13, 0x7B,
-0xFE, 0x34, 0x20, 0xF3, 0x11, 0xD8, 0x00, 0x06, 0x08, 0x1A, 0x13, 0x22, 0x23, 0x05, 0x20, 0xF9,
-0x3E, 0x19, 0xEA, 0x10, 0x99, 0x21, 0x2F, 0x99, 0x0E, 0x0C, 0x3D, 0x28, 0x08, 0x32, 0x0D, 0x20,
-0xF9, 0x2E, 0x0F, 0x18, 0xF3, 0x67, 0x3E, 0x64, 0x57, 0xE0, 0x42, 0x3E, 0x91, 0xE0, 0x40, 0x04,
-0x1E, 0x02, 0x0E, 0x0C, 0xF0, 0x44, 0xFE, 0x90, 0x20, 0xFA, 0x0D, 0x20, 0xF7, 0x1D, 0x20, 0xF2,
-0x0E, 0x13, 0x24, 0x7C, 0x1E, 0x83, 0xFE, 0x62, 0x28, 0x06, 0x1E, 0xC1, 0xFE, 0x64, 0x20, 0x06,
-0x7B, 0xE2, 0x0C, 0x3E, 0x87, 0xF2, 0xF0, 0x42, 0x90, 0xE0, 0x42, 0x15, 0x20, 0xD2, 0x05, 0x20,
-0x4F, 0x16, 0x20, 0x18, 0xCB, 0x4F, 0x06, 0x04, 0xC5, 0xCB, 0x11, 0x17, 0xC1, 0xCB, 0x11, 0x17,
-0x05, 0x20, 0xF5, 0x22, 0x23, 0x22, 0x23, 0xC9, 0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B,
-0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D, 0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E,
-0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99, 0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC,
-0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E, 0x3c, 0x42, 0xB9, 0xA5, 0xB9, 0xA5, 0x42, 0x4C,
-0x21, 0x04, 0x01, 0x11, 0xA8, 0x00, 0x1A, 0x13, 0xBE, 0x20, 0xFE, 0x23, 0x7D, 0xFE, 0x34, 0x20,
-0xF5, 0x06, 0x19, 0x78, 0x86, 0x23, 0x05, 0x20, 0xFB, 0x86, 0x20, 0xFE, 0x3E, 0x01, 0xE0, 0x50
+/// Joypad register
+pub const P1_ADDR: u16 = 0xFF00;
+/// Serial transfer data
+pub const SB_ADDR: u16 = 0xFF01;
+/// Serial transfer control
+pub const SC_ADDR: u16 = 0xFF02;
+/// CGB speed switch register
+pub const KEY1_ADDR: u16 = 0xFF4D;
+/// Writing to this register unmaps the boot rom
+pub const BOOT_OFF_ADDR: u16 = 0xFF50;
+/// Sound registers (NR10-NR52), stored as there is no APU yet
+const SOUND_START: u16 = 0xFF10;
+const SOUND_END: u16 = 0xFF26;
+/// Samples of the wave channel
+const WAVE_RAM_START: u16 = 0xFF30;
+const WAVE_RAM_END: u16 = 0xFF3F;
+
+/// Bits of the sound registers that can't be read back and read as 1, 0xFF for the unused
+/// addresses. The channel status bits of NR52 read as 0 as no channel ever plays.
+const SOUND_READ_MASKS: [u8; (SOUND_END - SOUND_START + 1) as usize] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // NR21-NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // NR41-NR44
+    0x00, 0x00, 0x70, // NR50-NR52
 ];
 
+pub const WRAM_SIZE: usize = 0x2000;
+const IO_SIZE: usize = 0x80;
+const HRAM_SIZE: usize = 0x7F;
+
+/// The memory bus, every access is routed to the component mapped at the address.
 pub struct MMU {
-    memory: [u8; 65536],
+    /// Boot rom image, mapped over 0x0000-0x00FF (and 0x0200-0x08FF on CGB) until FF50 is written
+    boot_rom: Vec<u8>,
     in_bios: bool,
+    pub cartridge: Option<Cartridge>,
+    /// Owns the VRAM and the OAM
+    pub ppu: Ppu,
+    pub wram: [u8; WRAM_SIZE],
+    /// IO registers without a dedicated component
+    io: [u8; IO_SIZE],
+    hram: [u8; HRAM_SIZE],
+    pub timer: Timer,
+    pub dma: OamDma,
+    /// Last byte read by the DMA, seen by the cpu reads conflicting with the transfer
+    dma_byte: u8,
+    /// IE register (0xFFFF)
+    pub interrupt_enable: u8,
+    /// IF register (0xFF0F)
+    pub interrupt_flag: u8,
+    /// Running a CGB cartridge, enables the CGB only registers
+    pub cgb_mode: bool,
+    /// KEY1 bit 7, the CGB cpu runs at 8MHz
+    pub double_speed: bool,
+    /// KEY1 bit 0, the next STOP switches the speed
+    pub prepare_speed_switch: bool,
 }
 
 impl MMU {
-    pub(crate) fn load_rom(&mut self, rom_bytes: &[u8]) {
-        self.memory[200..rom_bytes.len()+200].copy_from_slice(rom_bytes);
+    pub(crate) fn load_rom(&mut self, rom_bytes: &[u8]) -> Result<(), EmulationError> {
+        self.cartridge = Some(Cartridge::new(rom_bytes)?);
+        Ok(())
+    }
+
+    /// Map the boot rom image of the model over the start of the cartridge.
+    pub(crate) fn load_boot_rom(&mut self, model: Model, boot_rom: &[u8]) -> Result<(), EmulationError> {
+        let expected = model.boot_rom_size();
+        if boot_rom.len() != expected {
+            return Err(EmulationError::InvalidBootRom { size: boot_rom.len(), expected });
+        }
+        self.boot_rom = boot_rom.to_vec();
+        self.in_bios = true;
+        Ok(())
+    }
+
+    /// Same as the write to 0xFF50 done at the end of the boot rom.
+    pub(crate) fn unmap_boot_rom(&mut self) {
+        self.in_bios = false;
+    }
+
+    /// Raise the interrupt in the IF register, it will be serviced by the cpu once enabled in IE.
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt_flag |= interrupt.bits();
+    }
+
+    /// Interrupts that are both requested and enabled.
+    #[inline]
+    pub fn pending_interrupts(&self) -> u8 {
+        self.interrupt_enable & self.interrupt_flag & Interrupt::all().bits()
+    }
+
+    /// Advance the components clocked by the bus by the cycles the cpu just took.
+    pub fn tick(&mut self, cycles: u16) {
+        if self.timer.tick(cycles) {
+            self.request_interrupt(Interrupt::TIMER);
+        }
+        // The DMA follows the cpu speed
+        for index in self.dma.tick(cycles) {
+            self.dma_byte = self.read_bus(self.dma.source_addr(index));
+            self.ppu.oam[index as usize] = self.dma_byte;
+        }
+        // The PPU and the cartridge clocks don't follow the cpu speed
+        let cycles = cycles >> self.double_speed as u8;
+        let interrupts = self.ppu.tick(cycles);
+        self.request_interrupt(interrupts);
+        if let Some(cartridge) = self.cartridge.as_mut() {
+            cartridge.tick(cycles);
+        }
     }
 }
 
 impl Default for MMU {
     fn default() -> Self {
-        let mut memory = [0u8;65536];
-        let in_bios = true;
-        memory[0..256].copy_from_slice(&BIOS);
-
         MMU {
-            memory,
-            in_bios
+            boot_rom: BIOS.to_vec(),
+            in_bios: true,
+            cartridge: None,
+            ppu: Ppu::default(),
+            wram: [0; WRAM_SIZE],
+            io: [0; IO_SIZE],
+            hram: [0; HRAM_SIZE],
+            timer: Timer::default(),
+            dma: OamDma::default(),
+            dma_byte: 0xFF,
+            interrupt_enable: 0,
+            interrupt_flag: 0,
+            cgb_mode: false,
+            double_speed: false,
+            prepare_speed_switch: false,
         }
     }
 }
@@ -46,19 +147,68 @@ impl Default for MMU {
 impl MMU {
     #[inline(always)]
     pub fn read<const BYTES:usize>(&self, addr: MMUAddress) ->[u8; BYTES] {
-        if BYTES == 1 {
-            [self.memory[addr as usize]; BYTES]
-        }else {
-            let mut res = [0; BYTES];
-            res[0] = self.memory[addr as usize];
-            res[1] = self.memory[addr as usize + 1];
-            res
+        let mut res = [0; BYTES];
+        for (i, byte) in res.iter_mut().enumerate() {
+            *byte = self.rb(addr.wrapping_add(i as u16));
         }
+        res
     }
 
     #[inline]
     pub fn rb(&self, addr: MMUAddress) -> u8{
-        self.read::<1>(addr)[0]
+        match addr {
+            0x0000..=0xFDFF if self.dma.active() && self.dma.conflicts(addr) => self.dma_byte,
+            0xFE00..=0xFEFF if self.dma.active() => 0xFF,
+            // The PPU is reading the OAM
+            0xFE00..=0xFE9F if self.ppu.oam_blocked() => 0xFF,
+            _ => self.read_bus(addr),
+        }
+    }
+
+    /// Read without the restrictions of the DMA and the PPU.
+    fn read_bus(&self, addr: MMUAddress) -> u8 {
+        match addr {
+            0x0000..=0x00FF if self.in_bios => self.boot_rom[addr as usize],
+            // The CGB boot rom is split around the cartridge header
+            0x0200..=0x08FF if self.in_bios && self.boot_rom.len() > 0x100 => {
+                self.boot_rom[addr as usize]
+            }
+            0x0000..=0x7FFF => self.cartridge.as_ref().map_or(0xFF, |c| c.read_rom(addr)),
+            0x8000..=0x9FFF => self.ppu.vram[addr as usize - 0x8000],
+            0xA000..=0xBFFF => self.cartridge.as_ref().map_or(0xFF, |c| c.read_ram(addr)),
+            0xC000..=0xDFFF => self.wram[addr as usize - 0xC000],
+            // Echo of 0xC000-0xDDFF
+            0xE000..=0xFDFF => self.wram[addr as usize - 0xE000],
+            0xFE00..=0xFE9F => self.ppu.oam[addr as usize - 0xFE00],
+            // Unusable area
+            0xFEA0..=0xFEFF => 0x00,
+            0xFF00..=0xFF7F => self.read_io(addr),
+            0xFF80..=0xFFFE => self.hram[addr as usize - 0xFF80],
+            IE_ADDR => self.interrupt_enable,
+        }
+    }
+
+    fn read_io(&self, addr: MMUAddress) -> u8 {
+        let io = self.io[addr as usize - 0xFF00];
+        match addr {
+            // No button is pressed, only the selection bits can be read back
+            P1_ADDR => 0xC0 | (io & 0x30) | 0x0F,
+            SB_ADDR => io,
+            SC_ADDR => io | 0x7E,
+            DIV_ADDR..=TAC_ADDR => self.timer.read(addr),
+            // The 3 upper bits of IF are unused and always read as 1
+            IF_ADDR => self.interrupt_flag | 0xE0,
+            LCDC_ADDR..=LYC_ADDR | BGP_ADDR..=WX_ADDR => self.ppu.read(addr),
+            KEY1_ADDR if self.cgb_mode => {
+                0x7E | (self.double_speed as u8) << 7 | self.prepare_speed_switch as u8
+            }
+            KEY1_ADDR => 0xFF,
+            DMA_ADDR => self.dma.source,
+            SOUND_START..=SOUND_END => io | SOUND_READ_MASKS[(addr - SOUND_START) as usize],
+            WAVE_RAM_START..=WAVE_RAM_END => io,
+            // Unmapped, BOOT_OFF_ADDR can't be read back either
+            _ => 0xFF,
+        }
     }
 
     #[inline]
@@ -68,13 +218,72 @@ impl MMU {
 
     #[inline(always)]
     pub fn write<const BYTES:usize>(&mut self, addr: MMUAddress, val: [u8; BYTES]) {
-        let addr = addr as usize;
-        self.memory[addr..addr+ BYTES].copy_from_slice(&val);
+        for (i, byte) in val.iter().enumerate() {
+            self.wb(addr.wrapping_add(i as u16), *byte);
+        }
     }
 
     #[inline]
     pub fn wb (&mut self, addr: MMUAddress, val: u8) {
-        self.write(addr, [val])
+        match addr {
+            0x0000..=0xFDFF if self.dma.active() && self.dma.conflicts(addr) => {}
+            0xFE00..=0xFEFF if self.dma.active() => {}
+            0xFE00..=0xFE9F if self.ppu.oam_blocked() => {}
+            _ => self.write_bus(addr, val),
+        }
+    }
+
+    fn write_bus(&mut self, addr: MMUAddress, val: u8) {
+        match addr {
+            // Writes to the rom area go to the cartridge controller
+            0x0000..=0x7FFF => {
+                if let Some(cartridge) = self.cartridge.as_mut() {
+                    cartridge.write_rom(addr, val)
+                }
+            }
+            0x8000..=0x9FFF => self.ppu.vram[addr as usize - 0x8000] = val,
+            0xA000..=0xBFFF => {
+                if let Some(cartridge) = self.cartridge.as_mut() {
+                    cartridge.write_ram(addr, val)
+                }
+            }
+            0xC000..=0xDFFF => self.wram[addr as usize - 0xC000] = val,
+            0xE000..=0xFDFF => self.wram[addr as usize - 0xE000] = val,
+            0xFE00..=0xFE9F => self.ppu.oam[addr as usize - 0xFE00] = val,
+            0xFEA0..=0xFEFF => {}
+            0xFF00..=0xFF7F => self.write_io(addr, val),
+            0xFF80..=0xFFFE => self.hram[addr as usize - 0xFF80] = val,
+            IE_ADDR => self.interrupt_enable = val,
+        }
+    }
+
+    fn write_io(&mut self, addr: MMUAddress, val: u8) {
+        match addr {
+            DIV_ADDR..=TAC_ADDR => self.timer.write(addr, val),
+            SC_ADDR if val & 0x81 == 0x81 => {
+                // Transfer with the internal clock and nothing connected: completes at once and
+                // shifts in 1s
+                debug!("Serial out: {:#04x}", self.io[(SB_ADDR - 0xFF00) as usize]);
+                self.io[(SB_ADDR - 0xFF00) as usize] = 0xFF;
+                self.io[(SC_ADDR - 0xFF00) as usize] = val & 0x7F;
+                self.request_interrupt(Interrupt::SERIAL);
+            }
+            IF_ADDR => self.interrupt_flag = val & Interrupt::all().bits(),
+            LCDC_ADDR..=LYC_ADDR | BGP_ADDR..=WX_ADDR => {
+                let interrupts = self.ppu.write(addr, val);
+                self.request_interrupt(interrupts);
+            }
+            DMA_ADDR => self.dma.start(val),
+            KEY1_ADDR => self.prepare_speed_switch = self.cgb_mode && val & 1 != 0,
+            BOOT_OFF_ADDR if val != 0 && self.in_bios => {
+                debug!("Boot rom unmapped");
+                self.in_bios = false;
+            }
+            P1_ADDR | SB_ADDR | SC_ADDR | SOUND_START..=SOUND_END | WAVE_RAM_START..=WAVE_RAM_END => {
+                self.io[addr as usize - 0xFF00] = val
+            }
+            _ => {}
+        }
     }
 
     #[inline]
@@ -82,3 +291,91 @@ impl MMU {
         self.write(addr, val.to_le_bytes());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_read_back() {
+        // (address, value written, value read)
+        let cases = [
+            // Unmapped
+            (0xFF03, 0x12, 0xFF),
+            (0xFF08, 0x12, 0xFF),
+            (0xFF0E, 0x00, 0xFF),
+            (0xFF15, 0x00, 0xFF),
+            (0xFF27, 0x00, 0xFF),
+            (0xFF4C, 0x00, 0xFF),
+            (0xFF4F, 0x00, 0xFF),
+            (0xFF7F, 0x00, 0xFF),
+            (BOOT_OFF_ADDR, 0x00, 0xFF),
+            // KEY1 only exists in CGB mode
+            (KEY1_ADDR, 0x01, 0xFF),
+            // The unused bits read as 1
+            (IF_ADDR, 0x00, 0xE0),
+            (IF_ADDR, 0xFF, 0xFF),
+            (SC_ADDR, 0x00, 0x7E),
+            (TAC_ADDR, 0x00, 0xF8),
+            // STAT with LY = LYC
+            (0xFF41, 0x00, 0x84),
+            (0xFF10, 0x00, 0x80),
+            (0xFF12, 0x12, 0x12),
+            // The frequencies and lengths are write only
+            (0xFF11, 0x80, 0xBF),
+            (0xFF13, 0x12, 0xFF),
+            (0xFF14, 0x00, 0xBF),
+            (0xFF26, 0x80, 0xF0),
+            (0xFF30, 0x12, 0x12),
+            (0xFF3F, 0x34, 0x34),
+        ];
+        for (addr, val, read) in cases {
+            let mut mmu = MMU::default();
+            mmu.wb(addr, val);
+            assert_eq!(mmu.rb(addr), read, "{:#04x} to {:#06x}", val, addr);
+        }
+    }
+
+    #[test]
+    fn serial_transfer_without_link() {
+        // (SC written, SB after, SC after, interrupt)
+        let cases = [
+            // Internal clock: completes at once, nothing connected shifts in 1s
+            (0x81, 0xFF, 0x7F, true),
+            // External clock: waits for the other side
+            (0x80, 0x42, 0xFE, false),
+            (0x01, 0x42, 0x7F, false),
+        ];
+        for (sc, sb, sc_after, interrupt) in cases {
+            let mut mmu = MMU::default();
+            mmu.wb(SB_ADDR, 0x42);
+            mmu.wb(SC_ADDR, sc);
+            assert_eq!(mmu.rb(SB_ADDR), sb, "SC {:#04x}", sc);
+            assert_eq!(mmu.rb(SC_ADDR), sc_after, "SC {:#04x}", sc);
+            assert_eq!(mmu.interrupt_flag & Interrupt::SERIAL.bits() != 0, interrupt, "SC {:#04x}", sc);
+        }
+    }
+
+    #[test]
+    fn oam_stays_blocked_when_the_dma_restarts() {
+        let mut mmu = MMU::default();
+        mmu.wram[..0xA0].fill(0x42);
+        mmu.wb(DMA_ADDR, 0xC0);
+        mmu.tick(4);
+        mmu.tick(8);
+        assert!(mmu.dma.active());
+
+        mmu.wb(DMA_ADDR, 0xC0);
+        mmu.tick(4);
+        assert_eq!(mmu.rb(0xFE00), 0xFF);
+        mmu.wb(0xFE00, 0x00);
+        mmu.wb(0xC000, 0x00);
+        assert_eq!(mmu.ppu.oam[0], 0x42);
+        assert_eq!(mmu.wram[0], 0x42);
+
+        // Start delay then the 160 bytes
+        mmu.tick(4 + 640);
+        assert!(!mmu.dma.active());
+        assert_eq!(mmu.rb(0xFE00), 0x42);
+    }
+}
@@ -1,304 +1,664 @@
-use crate::cpu::{Cpu, Flags};
+use crate::cpu::{Condition, Cpu, Reg16};
 use crate::mmu::MMU;
 
-#[repr(u16)]
-#[derive(Clone, Copy, Debug)]
-pub enum Bytes {
-    None,
-    One(u8),
-    Pair([u8;2])
+/// Behaviour of an opcode, the variant declares the immediate operand read after the opcode
+/// and the type the handler receives it as.
+#[derive(Clone, Copy)]
+pub enum Exec {
+    /// No immediate operand
+    None(fn(&mut Cpu, &mut MMU)),
+    /// 8 bits unsigned immediate (d8, a8)
+    U8(fn(&mut Cpu, &mut MMU, u8)),
+    /// 8 bits signed immediate (r8)
+    I8(fn(&mut Cpu, &mut MMU, i8)),
+    /// 16 bits little endian immediate (d16, a16)
+    U16(fn(&mut Cpu, &mut MMU, u16)),
+    /// Opcode that doesn't exist, it locks up the real hardware
+    Illegal,
 }
 
-impl Bytes {
-    pub fn get_one(self) -> u8 {
-        match self {
-            None => panic!("Cannot unwrap one byte on a none value"),
-            Bytes::One(value) => value,
-            Bytes::Pair(_) => panic!("Cannot unwrap one byte on a pair"),
+#[derive(Clone)]
+pub struct OpCode {
+    pub name: &'static str,
+    pub time: u16,
+    pub f: Exec,
+}
+
+impl OpCode {
+    /// Number of bytes following the opcode.
+    #[inline]
+    pub const fn operand_size(&self) -> u8 {
+        match self.f {
+            Exec::None(_) | Exec::Illegal => 0,
+            Exec::U8(_) | Exec::I8(_) => 1,
+            Exec::U16(_) => 2,
         }
     }
+}
+
+pub const OP_CODES: &[OpCode; 256] = &[
+    OpCode { name: "NOP", time: 4, f: Exec::None(|_, _| ()) }, // 0x00
+    OpCode { name: "LD BC,d16", time: 12, f: Exec::U16(|c, _, v| c.w16(Reg16::BC, v)) }, // 0x01
+    OpCode { name: "LD (BC),A", time: 8, f: Exec::None(|c, m| m.wb(c.r_bc(), c.a)) }, // 0x02
+    OpCode { name: "INC BC", time: 8, f: Exec::None(|c, _| c.inc16(Reg16::BC)) }, // 0x03
+    OpCode { name: "INC B", time: 4, f: Exec::None(|c, _| c.b = c.inc(c.b)) }, // 0x04
+    OpCode { name: "DEC B", time: 4, f: Exec::None(|c, _| c.b = c.dec(c.b)) }, // 0x05
+    OpCode { name: "LD B,d8", time: 8, f: Exec::U8(|c, _, v| c.b = v) }, // 0x06
+    OpCode { name: "RLCA", time: 4, f: Exec::None(|c, _| c.rotate_a(true, false)) }, // 0x07
+    OpCode { name: "LD (a16),SP", time: 20, f: Exec::U16(|c, m, v| m.ww(v, c.sp)) }, // 0x08
+    OpCode { name: "ADD HL,BC", time: 8, f: Exec::None(|c, _| c.add_hl(Reg16::BC)) }, // 0x09
+    OpCode { name: "LD A,(BC)", time: 8, f: Exec::None(|c, m| c.a = m.rb(c.r_bc())) }, // 0x0a
+    OpCode { name: "DEC BC", time: 8, f: Exec::None(|c, _| c.dec16(Reg16::BC)) }, // 0x0b
+    OpCode { name: "INC C", time: 4, f: Exec::None(|c, _| c.c = c.inc(c.c)) }, // 0x0c
+    OpCode { name: "DEC C", time: 4, f: Exec::None(|c, _| c.c = c.dec(c.c)) }, // 0x0d
+    OpCode { name: "LD C,d8", time: 8, f: Exec::U8(|c, _, v| c.c = v) }, // 0x0e
+    OpCode { name: "RRCA", time: 4, f: Exec::None(|c, _| c.rotate_a(false, false)) }, // 0x0f
+    OpCode { name: "STOP 0", time: 4, f: Exec::U8(|c, m, _| c.stop(m)) }, // 0x10
+    OpCode { name: "LD DE,d16", time: 12, f: Exec::U16(|c, _, v| c.w16(Reg16::DE, v)) }, // 0x11
+    OpCode { name: "LD (DE),A", time: 8, f: Exec::None(|c, m| m.wb(c.r_de(), c.a)) }, // 0x12
+    OpCode { name: "INC DE", time: 8, f: Exec::None(|c, _| c.inc16(Reg16::DE)) }, // 0x13
+    OpCode { name: "INC D", time: 4, f: Exec::None(|c, _| c.d = c.inc(c.d)) }, // 0x14
+    OpCode { name: "DEC D", time: 4, f: Exec::None(|c, _| c.d = c.dec(c.d)) }, // 0x15
+    OpCode { name: "LD D,d8", time: 8, f: Exec::U8(|c, _, v| c.d = v) }, // 0x16
+    OpCode { name: "RLA", time: 4, f: Exec::None(|c, _| c.rotate_a(true, true)) }, // 0x17
+    OpCode { name: "JR r8", time: 12, f: Exec::I8(|c, _, v| c.jr(Condition::Always, v)) }, // 0x18
+    OpCode { name: "ADD HL,DE", time: 8, f: Exec::None(|c, _| c.add_hl(Reg16::DE)) }, // 0x19
+    OpCode { name: "LD A,(DE)", time: 8, f: Exec::None(|c, m| c.a = m.rb(c.r_de())) }, // 0x1a
+    OpCode { name: "DEC DE", time: 8, f: Exec::None(|c, _| c.dec16(Reg16::DE)) }, // 0x1b
+    OpCode { name: "INC E", time: 4, f: Exec::None(|c, _| c.e = c.inc(c.e)) }, // 0x1c
+    OpCode { name: "DEC E", time: 4, f: Exec::None(|c, _| c.e = c.dec(c.e)) }, // 0x1d
+    OpCode { name: "LD E,d8", time: 8, f: Exec::U8(|c, _, v| c.e = v) }, // 0x1e
+    OpCode { name: "RRA", time: 4, f: Exec::None(|c, _| c.rotate_a(false, true)) }, // 0x1f
+    OpCode { name: "JR NZ,r8", time: 8, f: Exec::I8(|c, _, v| c.jr(Condition::NZ, v)) }, // 0x20
+    OpCode { name: "LD HL,d16", time: 12, f: Exec::U16(|c, _, v| c.w16(Reg16::HL, v)) }, // 0x21
+    OpCode { name: "LD (HL+),A", time: 8, f: Exec::None(|c, m| { m.wb(c.r_hl(), c.a); c.w_hl(c.r_hl().wrapping_add(1)) }) }, // 0x22
+    OpCode { name: "INC HL", time: 8, f: Exec::None(|c, _| c.inc16(Reg16::HL)) }, // 0x23
+    OpCode { name: "INC H", time: 4, f: Exec::None(|c, _| c.h = c.inc(c.h)) }, // 0x24
+    OpCode { name: "DEC H", time: 4, f: Exec::None(|c, _| c.h = c.dec(c.h)) }, // 0x25
+    OpCode { name: "LD H,d8", time: 8, f: Exec::U8(|c, _, v| c.h = v) }, // 0x26
+    OpCode { name: "DAA", time: 4, f: Exec::None(|c, _| c.daa()) }, // 0x27
+    OpCode { name: "JR Z,r8", time: 8, f: Exec::I8(|c, _, v| c.jr(Condition::Z, v)) }, // 0x28
+    OpCode { name: "ADD HL,HL", time: 8, f: Exec::None(|c, _| c.add_hl(Reg16::HL)) }, // 0x29
+    OpCode { name: "LD A,(HL+)", time: 8, f: Exec::None(|c, m| { c.a = m.rb(c.r_hl()); c.w_hl(c.r_hl().wrapping_add(1)) }) }, // 0x2a
+    OpCode { name: "DEC HL", time: 8, f: Exec::None(|c, _| c.dec16(Reg16::HL)) }, // 0x2b
+    OpCode { name: "INC L", time: 4, f: Exec::None(|c, _| c.l = c.inc(c.l)) }, // 0x2c
+    OpCode { name: "DEC L", time: 4, f: Exec::None(|c, _| c.l = c.dec(c.l)) }, // 0x2d
+    OpCode { name: "LD L,d8", time: 8, f: Exec::U8(|c, _, v| c.l = v) }, // 0x2e
+    OpCode { name: "CPL", time: 4, f: Exec::None(|c, _| c.cpl()) }, // 0x2f
+    OpCode { name: "JR NC,r8", time: 8, f: Exec::I8(|c, _, v| c.jr(Condition::NC, v)) }, // 0x30
+    OpCode { name: "LD SP,d16", time: 12, f: Exec::U16(|c, _, v| c.w16(Reg16::SP, v)) }, // 0x31
+    OpCode { name: "LD (HL-),A", time: 8, f: Exec::None(|c, m| { m.wb(c.r_hl(), c.a); c.w_hl(c.r_hl().wrapping_sub(1)) }) }, // 0x32
+    OpCode { name: "INC SP", time: 8, f: Exec::None(|c, _| c.inc16(Reg16::SP)) }, // 0x33
+    OpCode { name: "INC (HL)", time: 12, f: Exec::None(|c, m| { let v = c.inc(m.rb(c.r_hl())); m.wb(c.r_hl(), v) }) }, // 0x34
+    OpCode { name: "DEC (HL)", time: 12, f: Exec::None(|c, m| { let v = c.dec(m.rb(c.r_hl())); m.wb(c.r_hl(), v) }) }, // 0x35
+    OpCode { name: "LD (HL),d8", time: 12, f: Exec::U8(|c, m, v| m.wb(c.r_hl(), v)) }, // 0x36
+    OpCode { name: "SCF", time: 4, f: Exec::None(|c, _| c.scf()) }, // 0x37
+    OpCode { name: "JR C,r8", time: 8, f: Exec::I8(|c, _, v| c.jr(Condition::C, v)) }, // 0x38
+    OpCode { name: "ADD HL,SP", time: 8, f: Exec::None(|c, _| c.add_hl(Reg16::SP)) }, // 0x39
+    OpCode { name: "LD A,(HL-)", time: 8, f: Exec::None(|c, m| { c.a = m.rb(c.r_hl()); c.w_hl(c.r_hl().wrapping_sub(1)) }) }, // 0x3a
+    OpCode { name: "DEC SP", time: 8, f: Exec::None(|c, _| c.dec16(Reg16::SP)) }, // 0x3b
+    OpCode { name: "INC A", time: 4, f: Exec::None(|c, _| c.a = c.inc(c.a)) }, // 0x3c
+    OpCode { name: "DEC A", time: 4, f: Exec::None(|c, _| c.a = c.dec(c.a)) }, // 0x3d
+    OpCode { name: "LD A,d8", time: 8, f: Exec::U8(|c, _, v| c.a = v) }, // 0x3e
+    OpCode { name: "CCF", time: 4, f: Exec::None(|c, _| c.ccf()) }, // 0x3f
+    OpCode { name: "LD B,B", time: 4, f: Exec::None(|_, _| ()) }, // 0x40
+    OpCode { name: "LD B,C", time: 4, f: Exec::None(|c, _| c.b = c.c) }, // 0x41
+    OpCode { name: "LD B,D", time: 4, f: Exec::None(|c, _| c.b = c.d) }, // 0x42
+    OpCode { name: "LD B,E", time: 4, f: Exec::None(|c, _| c.b = c.e) }, // 0x43
+    OpCode { name: "LD B,H", time: 4, f: Exec::None(|c, _| c.b = c.h) }, // 0x44
+    OpCode { name: "LD B,L", time: 4, f: Exec::None(|c, _| c.b = c.l) }, // 0x45
+    OpCode { name: "LD B,(HL)", time: 8, f: Exec::None(|c, m| c.b = m.rb(c.r_hl())) }, // 0x46
+    OpCode { name: "LD B,A", time: 4, f: Exec::None(|c, _| c.b = c.a) }, // 0x47
+    OpCode { name: "LD C,B", time: 4, f: Exec::None(|c, _| c.c = c.b) }, // 0x48
+    OpCode { name: "LD C,C", time: 4, f: Exec::None(|_, _| ()) }, // 0x49
+    OpCode { name: "LD C,D", time: 4, f: Exec::None(|c, _| c.c = c.d) }, // 0x4a
+    OpCode { name: "LD C,E", time: 4, f: Exec::None(|c, _| c.c = c.e) }, // 0x4b
+    OpCode { name: "LD C,H", time: 4, f: Exec::None(|c, _| c.c = c.h) }, // 0x4c
+    OpCode { name: "LD C,L", time: 4, f: Exec::None(|c, _| c.c = c.l) }, // 0x4d
+    OpCode { name: "LD C,(HL)", time: 8, f: Exec::None(|c, m| c.c = m.rb(c.r_hl())) }, // 0x4e
+    OpCode { name: "LD C,A", time: 4, f: Exec::None(|c, _| c.c = c.a) }, // 0x4f
+    OpCode { name: "LD D,B", time: 4, f: Exec::None(|c, _| c.d = c.b) }, // 0x50
+    OpCode { name: "LD D,C", time: 4, f: Exec::None(|c, _| c.d = c.c) }, // 0x51
+    OpCode { name: "LD D,D", time: 4, f: Exec::None(|_, _| ()) }, // 0x52
+    OpCode { name: "LD D,E", time: 4, f: Exec::None(|c, _| c.d = c.e) }, // 0x53
+    OpCode { name: "LD D,H", time: 4, f: Exec::None(|c, _| c.d = c.h) }, // 0x54
+    OpCode { name: "LD D,L", time: 4, f: Exec::None(|c, _| c.d = c.l) }, // 0x55
+    OpCode { name: "LD D,(HL)", time: 8, f: Exec::None(|c, m| c.d = m.rb(c.r_hl())) }, // 0x56
+    OpCode { name: "LD D,A", time: 4, f: Exec::None(|c, _| c.d = c.a) }, // 0x57
+    OpCode { name: "LD E,B", time: 4, f: Exec::None(|c, _| c.e = c.b) }, // 0x58
+    OpCode { name: "LD E,C", time: 4, f: Exec::None(|c, _| c.e = c.c) }, // 0x59
+    OpCode { name: "LD E,D", time: 4, f: Exec::None(|c, _| c.e = c.d) }, // 0x5a
+    OpCode { name: "LD E,E", time: 4, f: Exec::None(|_, _| ()) }, // 0x5b
+    OpCode { name: "LD E,H", time: 4, f: Exec::None(|c, _| c.e = c.h) }, // 0x5c
+    OpCode { name: "LD E,L", time: 4, f: Exec::None(|c, _| c.e = c.l) }, // 0x5d
+    OpCode { name: "LD E,(HL)", time: 8, f: Exec::None(|c, m| c.e = m.rb(c.r_hl())) }, // 0x5e
+    OpCode { name: "LD E,A", time: 4, f: Exec::None(|c, _| c.e = c.a) }, // 0x5f
+    OpCode { name: "LD H,B", time: 4, f: Exec::None(|c, _| c.h = c.b) }, // 0x60
+    OpCode { name: "LD H,C", time: 4, f: Exec::None(|c, _| c.h = c.c) }, // 0x61
+    OpCode { name: "LD H,D", time: 4, f: Exec::None(|c, _| c.h = c.d) }, // 0x62
+    OpCode { name: "LD H,E", time: 4, f: Exec::None(|c, _| c.h = c.e) }, // 0x63
+    OpCode { name: "LD H,H", time: 4, f: Exec::None(|_, _| ()) }, // 0x64
+    OpCode { name: "LD H,L", time: 4, f: Exec::None(|c, _| c.h = c.l) }, // 0x65
+    OpCode { name: "LD H,(HL)", time: 8, f: Exec::None(|c, m| c.h = m.rb(c.r_hl())) }, // 0x66
+    OpCode { name: "LD H,A", time: 4, f: Exec::None(|c, _| c.h = c.a) }, // 0x67
+    OpCode { name: "LD L,B", time: 4, f: Exec::None(|c, _| c.l = c.b) }, // 0x68
+    OpCode { name: "LD L,C", time: 4, f: Exec::None(|c, _| c.l = c.c) }, // 0x69
+    OpCode { name: "LD L,D", time: 4, f: Exec::None(|c, _| c.l = c.d) }, // 0x6a
+    OpCode { name: "LD L,E", time: 4, f: Exec::None(|c, _| c.l = c.e) }, // 0x6b
+    OpCode { name: "LD L,H", time: 4, f: Exec::None(|c, _| c.l = c.h) }, // 0x6c
+    OpCode { name: "LD L,L", time: 4, f: Exec::None(|_, _| ()) }, // 0x6d
+    OpCode { name: "LD L,(HL)", time: 8, f: Exec::None(|c, m| c.l = m.rb(c.r_hl())) }, // 0x6e
+    OpCode { name: "LD L,A", time: 4, f: Exec::None(|c, _| c.l = c.a) }, // 0x6f
+    OpCode { name: "LD (HL),B", time: 8, f: Exec::None(|c, m| m.wb(c.r_hl(), c.b)) }, // 0x70
+    OpCode { name: "LD (HL),C", time: 8, f: Exec::None(|c, m| m.wb(c.r_hl(), c.c)) }, // 0x71
+    OpCode { name: "LD (HL),D", time: 8, f: Exec::None(|c, m| m.wb(c.r_hl(), c.d)) }, // 0x72
+    OpCode { name: "LD (HL),E", time: 8, f: Exec::None(|c, m| m.wb(c.r_hl(), c.e)) }, // 0x73
+    OpCode { name: "LD (HL),H", time: 8, f: Exec::None(|c, m| m.wb(c.r_hl(), c.h)) }, // 0x74
+    OpCode { name: "LD (HL),L", time: 8, f: Exec::None(|c, m| m.wb(c.r_hl(), c.l)) }, // 0x75
+    OpCode { name: "HALT", time: 4, f: Exec::None(|c, m| c.halt(m)) }, // 0x76
+    OpCode { name: "LD (HL),A", time: 8, f: Exec::None(|c, m| m.wb(c.r_hl(), c.a)) }, // 0x77
+    OpCode { name: "LD A,B", time: 4, f: Exec::None(|c, _| c.a = c.b) }, // 0x78
+    OpCode { name: "LD A,C", time: 4, f: Exec::None(|c, _| c.a = c.c) }, // 0x79
+    OpCode { name: "LD A,D", time: 4, f: Exec::None(|c, _| c.a = c.d) }, // 0x7a
+    OpCode { name: "LD A,E", time: 4, f: Exec::None(|c, _| c.a = c.e) }, // 0x7b
+    OpCode { name: "LD A,H", time: 4, f: Exec::None(|c, _| c.a = c.h) }, // 0x7c
+    OpCode { name: "LD A,L", time: 4, f: Exec::None(|c, _| c.a = c.l) }, // 0x7d
+    OpCode { name: "LD A,(HL)", time: 8, f: Exec::None(|c, m| c.a = m.rb(c.r_hl())) }, // 0x7e
+    OpCode { name: "LD A,A", time: 4, f: Exec::None(|_, _| ()) }, // 0x7f
+    OpCode { name: "ADD A,B", time: 4, f: Exec::None(|c, _| c.add_a(c.b)) }, // 0x80
+    OpCode { name: "ADD A,C", time: 4, f: Exec::None(|c, _| c.add_a(c.c)) }, // 0x81
+    OpCode { name: "ADD A,D", time: 4, f: Exec::None(|c, _| c.add_a(c.d)) }, // 0x82
+    OpCode { name: "ADD A,E", time: 4, f: Exec::None(|c, _| c.add_a(c.e)) }, // 0x83
+    OpCode { name: "ADD A,H", time: 4, f: Exec::None(|c, _| c.add_a(c.h)) }, // 0x84
+    OpCode { name: "ADD A,L", time: 4, f: Exec::None(|c, _| c.add_a(c.l)) }, // 0x85
+    OpCode { name: "ADD A,(HL)", time: 8, f: Exec::None(|c, m| c.add_a(m.rb(c.r_hl()))) }, // 0x86
+    OpCode { name: "ADD A,A", time: 4, f: Exec::None(|c, _| c.add_a(c.a)) }, // 0x87
+    OpCode { name: "ADC A,B", time: 4, f: Exec::None(|c, _| c.adc_a(c.b)) }, // 0x88
+    OpCode { name: "ADC A,C", time: 4, f: Exec::None(|c, _| c.adc_a(c.c)) }, // 0x89
+    OpCode { name: "ADC A,D", time: 4, f: Exec::None(|c, _| c.adc_a(c.d)) }, // 0x8a
+    OpCode { name: "ADC A,E", time: 4, f: Exec::None(|c, _| c.adc_a(c.e)) }, // 0x8b
+    OpCode { name: "ADC A,H", time: 4, f: Exec::None(|c, _| c.adc_a(c.h)) }, // 0x8c
+    OpCode { name: "ADC A,L", time: 4, f: Exec::None(|c, _| c.adc_a(c.l)) }, // 0x8d
+    OpCode { name: "ADC A,(HL)", time: 8, f: Exec::None(|c, m| c.adc_a(m.rb(c.r_hl()))) }, // 0x8e
+    OpCode { name: "ADC A,A", time: 4, f: Exec::None(|c, _| c.adc_a(c.a)) }, // 0x8f
+    OpCode { name: "SUB B", time: 4, f: Exec::None(|c, _| c.sub_a(c.b)) }, // 0x90
+    OpCode { name: "SUB C", time: 4, f: Exec::None(|c, _| c.sub_a(c.c)) }, // 0x91
+    OpCode { name: "SUB D", time: 4, f: Exec::None(|c, _| c.sub_a(c.d)) }, // 0x92
+    OpCode { name: "SUB E", time: 4, f: Exec::None(|c, _| c.sub_a(c.e)) }, // 0x93
+    OpCode { name: "SUB H", time: 4, f: Exec::None(|c, _| c.sub_a(c.h)) }, // 0x94
+    OpCode { name: "SUB L", time: 4, f: Exec::None(|c, _| c.sub_a(c.l)) }, // 0x95
+    OpCode { name: "SUB (HL)", time: 8, f: Exec::None(|c, m| c.sub_a(m.rb(c.r_hl()))) }, // 0x96
+    OpCode { name: "SUB A", time: 4, f: Exec::None(|c, _| c.sub_a(c.a)) }, // 0x97
+    OpCode { name: "SBC A,B", time: 4, f: Exec::None(|c, _| c.sbc_a(c.b)) }, // 0x98
+    OpCode { name: "SBC A,C", time: 4, f: Exec::None(|c, _| c.sbc_a(c.c)) }, // 0x99
+    OpCode { name: "SBC A,D", time: 4, f: Exec::None(|c, _| c.sbc_a(c.d)) }, // 0x9a
+    OpCode { name: "SBC A,E", time: 4, f: Exec::None(|c, _| c.sbc_a(c.e)) }, // 0x9b
+    OpCode { name: "SBC A,H", time: 4, f: Exec::None(|c, _| c.sbc_a(c.h)) }, // 0x9c
+    OpCode { name: "SBC A,L", time: 4, f: Exec::None(|c, _| c.sbc_a(c.l)) }, // 0x9d
+    OpCode { name: "SBC A,(HL)", time: 8, f: Exec::None(|c, m| c.sbc_a(m.rb(c.r_hl()))) }, // 0x9e
+    OpCode { name: "SBC A,A", time: 4, f: Exec::None(|c, _| c.sbc_a(c.a)) }, // 0x9f
+    OpCode { name: "AND B", time: 4, f: Exec::None(|c, _| c.and_a(c.b)) }, // 0xa0
+    OpCode { name: "AND C", time: 4, f: Exec::None(|c, _| c.and_a(c.c)) }, // 0xa1
+    OpCode { name: "AND D", time: 4, f: Exec::None(|c, _| c.and_a(c.d)) }, // 0xa2
+    OpCode { name: "AND E", time: 4, f: Exec::None(|c, _| c.and_a(c.e)) }, // 0xa3
+    OpCode { name: "AND H", time: 4, f: Exec::None(|c, _| c.and_a(c.h)) }, // 0xa4
+    OpCode { name: "AND L", time: 4, f: Exec::None(|c, _| c.and_a(c.l)) }, // 0xa5
+    OpCode { name: "AND (HL)", time: 8, f: Exec::None(|c, m| c.and_a(m.rb(c.r_hl()))) }, // 0xa6
+    OpCode { name: "AND A", time: 4, f: Exec::None(|c, _| c.and_a(c.a)) }, // 0xa7
+    OpCode { name: "XOR B", time: 4, f: Exec::None(|c, _| c.xor_a(c.b)) }, // 0xa8
+    OpCode { name: "XOR C", time: 4, f: Exec::None(|c, _| c.xor_a(c.c)) }, // 0xa9
+    OpCode { name: "XOR D", time: 4, f: Exec::None(|c, _| c.xor_a(c.d)) }, // 0xaa
+    OpCode { name: "XOR E", time: 4, f: Exec::None(|c, _| c.xor_a(c.e)) }, // 0xab
+    OpCode { name: "XOR H", time: 4, f: Exec::None(|c, _| c.xor_a(c.h)) }, // 0xac
+    OpCode { name: "XOR L", time: 4, f: Exec::None(|c, _| c.xor_a(c.l)) }, // 0xad
+    OpCode { name: "XOR (HL)", time: 8, f: Exec::None(|c, m| c.xor_a(m.rb(c.r_hl()))) }, // 0xae
+    OpCode { name: "XOR A", time: 4, f: Exec::None(|c, _| c.xor_a(c.a)) }, // 0xaf
+    OpCode { name: "OR B", time: 4, f: Exec::None(|c, _| c.or_a(c.b)) }, // 0xb0
+    OpCode { name: "OR C", time: 4, f: Exec::None(|c, _| c.or_a(c.c)) }, // 0xb1
+    OpCode { name: "OR D", time: 4, f: Exec::None(|c, _| c.or_a(c.d)) }, // 0xb2
+    OpCode { name: "OR E", time: 4, f: Exec::None(|c, _| c.or_a(c.e)) }, // 0xb3
+    OpCode { name: "OR H", time: 4, f: Exec::None(|c, _| c.or_a(c.h)) }, // 0xb4
+    OpCode { name: "OR L", time: 4, f: Exec::None(|c, _| c.or_a(c.l)) }, // 0xb5
+    OpCode { name: "OR (HL)", time: 8, f: Exec::None(|c, m| c.or_a(m.rb(c.r_hl()))) }, // 0xb6
+    OpCode { name: "OR A", time: 4, f: Exec::None(|c, _| c.or_a(c.a)) }, // 0xb7
+    OpCode { name: "CP B", time: 4, f: Exec::None(|c, _| c.cp_a(c.b)) }, // 0xb8
+    OpCode { name: "CP C", time: 4, f: Exec::None(|c, _| c.cp_a(c.c)) }, // 0xb9
+    OpCode { name: "CP D", time: 4, f: Exec::None(|c, _| c.cp_a(c.d)) }, // 0xba
+    OpCode { name: "CP E", time: 4, f: Exec::None(|c, _| c.cp_a(c.e)) }, // 0xbb
+    OpCode { name: "CP H", time: 4, f: Exec::None(|c, _| c.cp_a(c.h)) }, // 0xbc
+    OpCode { name: "CP L", time: 4, f: Exec::None(|c, _| c.cp_a(c.l)) }, // 0xbd
+    OpCode { name: "CP (HL)", time: 8, f: Exec::None(|c, m| c.cp_a(m.rb(c.r_hl()))) }, // 0xbe
+    OpCode { name: "CP A", time: 4, f: Exec::None(|c, _| c.cp_a(c.a)) }, // 0xbf
+    OpCode { name: "RET NZ", time: 8, f: Exec::None(|c, m| c.ret_cc(Condition::NZ, m)) }, // 0xc0
+    OpCode { name: "POP BC", time: 12, f: Exec::None(|c, m| c.pop16(m, Reg16::BC)) }, // 0xc1
+    OpCode { name: "JP NZ,a16", time: 12, f: Exec::U16(|c, _, v| c.jp(Condition::NZ, v)) }, // 0xc2
+    OpCode { name: "JP a16", time: 16, f: Exec::U16(|c, _, v| c.jp(Condition::Always, v)) }, // 0xc3
+    OpCode { name: "CALL NZ,a16", time: 12, f: Exec::U16(|c, m, v| c.call(Condition::NZ, v, m)) }, // 0xc4
+    OpCode { name: "PUSH BC", time: 16, f: Exec::None(|c, m| c.push16(m, Reg16::BC)) }, // 0xc5
+    OpCode { name: "ADD A,d8", time: 8, f: Exec::U8(|c, _, v| c.add_a(v)) }, // 0xc6
+    OpCode { name: "RST 00H", time: 16, f: Exec::None(|c, m| c.rst(0x0, m)) }, // 0xc7
+    OpCode { name: "RET Z", time: 8, f: Exec::None(|c, m| c.ret_cc(Condition::Z, m)) }, // 0xc8
+    OpCode { name: "RET", time: 16, f: Exec::None(|c, m| c.ret(m)) }, // 0xc9
+    OpCode { name: "JP Z,a16", time: 12, f: Exec::U16(|c, _, v| c.jp(Condition::Z, v)) }, // 0xca
+    // The prefix is decoded by Cpu::run which then looks up CB_OP_CODES
+    OpCode { name: "PREFIX CB", time: 4, f: Exec::None(|_, _| unreachable!("CB prefix is dispatched by the cpu")) }, // 0xcb
+    OpCode { name: "CALL Z,a16", time: 12, f: Exec::U16(|c, m, v| c.call(Condition::Z, v, m)) }, // 0xcc
+    OpCode { name: "CALL a16", time: 24, f: Exec::U16(|c, m, v| c.call(Condition::Always, v, m)) }, // 0xcd
+    OpCode { name: "ADC A,d8", time: 8, f: Exec::U8(|c, _, v| c.adc_a(v)) }, // 0xce
+    OpCode { name: "RST 08H", time: 16, f: Exec::None(|c, m| c.rst(0x8, m)) }, // 0xcf
+    OpCode { name: "RET NC", time: 8, f: Exec::None(|c, m| c.ret_cc(Condition::NC, m)) }, // 0xd0
+    OpCode { name: "POP DE", time: 12, f: Exec::None(|c, m| c.pop16(m, Reg16::DE)) }, // 0xd1
+    OpCode { name: "JP NC,a16", time: 12, f: Exec::U16(|c, _, v| c.jp(Condition::NC, v)) }, // 0xd2
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xd3
+    OpCode { name: "CALL NC,a16", time: 12, f: Exec::U16(|c, m, v| c.call(Condition::NC, v, m)) }, // 0xd4
+    OpCode { name: "PUSH DE", time: 16, f: Exec::None(|c, m| c.push16(m, Reg16::DE)) }, // 0xd5
+    OpCode { name: "SUB d8", time: 8, f: Exec::U8(|c, _, v| c.sub_a(v)) }, // 0xd6
+    OpCode { name: "RST 10H", time: 16, f: Exec::None(|c, m| c.rst(0x10, m)) }, // 0xd7
+    OpCode { name: "RET C", time: 8, f: Exec::None(|c, m| c.ret_cc(Condition::C, m)) }, // 0xd8
+    OpCode { name: "RETI", time: 16, f: Exec::None(|c, m| c.reti(m)) }, // 0xd9
+    OpCode { name: "JP C,a16", time: 12, f: Exec::U16(|c, _, v| c.jp(Condition::C, v)) }, // 0xda
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xdb
+    OpCode { name: "CALL C,a16", time: 12, f: Exec::U16(|c, m, v| c.call(Condition::C, v, m)) }, // 0xdc
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xdd
+    OpCode { name: "SBC A,d8", time: 8, f: Exec::U8(|c, _, v| c.sbc_a(v)) }, // 0xde
+    OpCode { name: "RST 18H", time: 16, f: Exec::None(|c, m| c.rst(0x18, m)) }, // 0xdf
+    OpCode { name: "LDH (a8),A", time: 12, f: Exec::U8(|c, m, v| m.wb(0xFF00 + v as u16, c.a)) }, // 0xe0
+    OpCode { name: "POP HL", time: 12, f: Exec::None(|c, m| c.pop16(m, Reg16::HL)) }, // 0xe1
+    OpCode { name: "LD (C),A", time: 8, f: Exec::None(|c, m| m.wb(0xFF00 + c.c as u16, c.a)) }, // 0xe2
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xe3
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xe4
+    OpCode { name: "PUSH HL", time: 16, f: Exec::None(|c, m| c.push16(m, Reg16::HL)) }, // 0xe5
+    OpCode { name: "AND d8", time: 8, f: Exec::U8(|c, _, v| c.and_a(v)) }, // 0xe6
+    OpCode { name: "RST 20H", time: 16, f: Exec::None(|c, m| c.rst(0x20, m)) }, // 0xe7
+    OpCode { name: "ADD SP,r8", time: 16, f: Exec::I8(|c, _, v| c.add_sp(v)) }, // 0xe8
+    OpCode { name: "JP (HL)", time: 4, f: Exec::None(|c, _| c.jmp(c.r_hl())) }, // 0xe9
+    OpCode { name: "LD (a16),A", time: 16, f: Exec::U16(|c, m, v| m.wb(v, c.a)) }, // 0xea
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xeb
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xec
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xed
+    OpCode { name: "XOR d8", time: 8, f: Exec::U8(|c, _, v| c.xor_a(v)) }, // 0xee
+    OpCode { name: "RST 28H", time: 16, f: Exec::None(|c, m| c.rst(0x28, m)) }, // 0xef
+    OpCode { name: "LDH A,(a8)", time: 12, f: Exec::U8(|c, m, v| c.a = m.rb(0xFF00 + v as u16)) }, // 0xf0
+    OpCode { name: "POP AF", time: 12, f: Exec::None(|c, m| c.pop16(m, Reg16::AF)) }, // 0xf1
+    OpCode { name: "LD A,(C)", time: 8, f: Exec::None(|c, m| c.a = m.rb(0xFF00 + c.c as u16)) }, // 0xf2
+    OpCode { name: "DI", time: 4, f: Exec::None(|c, _| { c.ime = false; c.ei_delay = false }) }, // 0xf3
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xf4
+    OpCode { name: "PUSH AF", time: 16, f: Exec::None(|c, m| c.push16(m, Reg16::AF)) }, // 0xf5
+    OpCode { name: "OR d8", time: 8, f: Exec::U8(|c, _, v| c.or_a(v)) }, // 0xf6
+    OpCode { name: "RST 30H", time: 16, f: Exec::None(|c, m| c.rst(0x30, m)) }, // 0xf7
+    OpCode { name: "LD HL,SP+r8", time: 12, f: Exec::I8(|c, _, v| c.ld_hl_sp(v)) }, // 0xf8
+    OpCode { name: "LD SP,HL", time: 8, f: Exec::None(|c, _| c.w16(Reg16::SP, c.r16(Reg16::HL))) }, // 0xf9
+    OpCode { name: "LD A,(a16)", time: 16, f: Exec::U16(|c, m, v| c.a = m.rb(v)) }, // 0xfa
+    OpCode { name: "EI", time: 4, f: Exec::None(|c, _| c.ei_delay = !c.ime) }, // 0xfb
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xfc
+    OpCode { name: "ILLEGAL", time: 4, f: Exec::Illegal }, // 0xfd
+    OpCode { name: "CP d8", time: 8, f: Exec::U8(|c, _, v| c.cp_a(v)) }, // 0xfe
+    OpCode { name: "RST 38H", time: 16, f: Exec::None(|c, m| c.rst(0x38, m)) }, // 0xff
+];
+
+/// Second page of instructions, selected by the 0xCB prefix. The timings include the prefix fetch.
+pub const CB_OP_CODES: &[OpCode; 256] = &[
+    OpCode { name: "RLC B", time: 8, f: Exec::None(|c, _| c.b = c.rotate(c.b, true, false)) }, // 0x00
+    OpCode { name: "RLC C", time: 8, f: Exec::None(|c, _| c.c = c.rotate(c.c, true, false)) }, // 0x01
+    OpCode { name: "RLC D", time: 8, f: Exec::None(|c, _| c.d = c.rotate(c.d, true, false)) }, // 0x02
+    OpCode { name: "RLC E", time: 8, f: Exec::None(|c, _| c.e = c.rotate(c.e, true, false)) }, // 0x03
+    OpCode { name: "RLC H", time: 8, f: Exec::None(|c, _| c.h = c.rotate(c.h, true, false)) }, // 0x04
+    OpCode { name: "RLC L", time: 8, f: Exec::None(|c, _| c.l = c.rotate(c.l, true, false)) }, // 0x05
+    OpCode { name: "RLC (HL)", time: 16, f: Exec::None(|c, m| { let v = c.rotate(m.rb(c.r_hl()), true, false); m.wb(c.r_hl(), v) }) }, // 0x06
+    OpCode { name: "RLC A", time: 8, f: Exec::None(|c, _| c.a = c.rotate(c.a, true, false)) }, // 0x07
+    OpCode { name: "RRC B", time: 8, f: Exec::None(|c, _| c.b = c.rotate(c.b, false, false)) }, // 0x08
+    OpCode { name: "RRC C", time: 8, f: Exec::None(|c, _| c.c = c.rotate(c.c, false, false)) }, // 0x09
+    OpCode { name: "RRC D", time: 8, f: Exec::None(|c, _| c.d = c.rotate(c.d, false, false)) }, // 0x0a
+    OpCode { name: "RRC E", time: 8, f: Exec::None(|c, _| c.e = c.rotate(c.e, false, false)) }, // 0x0b
+    OpCode { name: "RRC H", time: 8, f: Exec::None(|c, _| c.h = c.rotate(c.h, false, false)) }, // 0x0c
+    OpCode { name: "RRC L", time: 8, f: Exec::None(|c, _| c.l = c.rotate(c.l, false, false)) }, // 0x0d
+    OpCode { name: "RRC (HL)", time: 16, f: Exec::None(|c, m| { let v = c.rotate(m.rb(c.r_hl()), false, false); m.wb(c.r_hl(), v) }) }, // 0x0e
+    OpCode { name: "RRC A", time: 8, f: Exec::None(|c, _| c.a = c.rotate(c.a, false, false)) }, // 0x0f
+    OpCode { name: "RL B", time: 8, f: Exec::None(|c, _| c.b = c.rotate(c.b, true, true)) }, // 0x10
+    OpCode { name: "RL C", time: 8, f: Exec::None(|c, _| c.c = c.rotate(c.c, true, true)) }, // 0x11
+    OpCode { name: "RL D", time: 8, f: Exec::None(|c, _| c.d = c.rotate(c.d, true, true)) }, // 0x12
+    OpCode { name: "RL E", time: 8, f: Exec::None(|c, _| c.e = c.rotate(c.e, true, true)) }, // 0x13
+    OpCode { name: "RL H", time: 8, f: Exec::None(|c, _| c.h = c.rotate(c.h, true, true)) }, // 0x14
+    OpCode { name: "RL L", time: 8, f: Exec::None(|c, _| c.l = c.rotate(c.l, true, true)) }, // 0x15
+    OpCode { name: "RL (HL)", time: 16, f: Exec::None(|c, m| { let v = c.rotate(m.rb(c.r_hl()), true, true); m.wb(c.r_hl(), v) }) }, // 0x16
+    OpCode { name: "RL A", time: 8, f: Exec::None(|c, _| c.a = c.rotate(c.a, true, true)) }, // 0x17
+    OpCode { name: "RR B", time: 8, f: Exec::None(|c, _| c.b = c.rotate(c.b, false, true)) }, // 0x18
+    OpCode { name: "RR C", time: 8, f: Exec::None(|c, _| c.c = c.rotate(c.c, false, true)) }, // 0x19
+    OpCode { name: "RR D", time: 8, f: Exec::None(|c, _| c.d = c.rotate(c.d, false, true)) }, // 0x1a
+    OpCode { name: "RR E", time: 8, f: Exec::None(|c, _| c.e = c.rotate(c.e, false, true)) }, // 0x1b
+    OpCode { name: "RR H", time: 8, f: Exec::None(|c, _| c.h = c.rotate(c.h, false, true)) }, // 0x1c
+    OpCode { name: "RR L", time: 8, f: Exec::None(|c, _| c.l = c.rotate(c.l, false, true)) }, // 0x1d
+    OpCode { name: "RR (HL)", time: 16, f: Exec::None(|c, m| { let v = c.rotate(m.rb(c.r_hl()), false, true); m.wb(c.r_hl(), v) }) }, // 0x1e
+    OpCode { name: "RR A", time: 8, f: Exec::None(|c, _| c.a = c.rotate(c.a, false, true)) }, // 0x1f
+    OpCode { name: "SLA B", time: 8, f: Exec::None(|c, _| c.b = c.sla(c.b)) }, // 0x20
+    OpCode { name: "SLA C", time: 8, f: Exec::None(|c, _| c.c = c.sla(c.c)) }, // 0x21
+    OpCode { name: "SLA D", time: 8, f: Exec::None(|c, _| c.d = c.sla(c.d)) }, // 0x22
+    OpCode { name: "SLA E", time: 8, f: Exec::None(|c, _| c.e = c.sla(c.e)) }, // 0x23
+    OpCode { name: "SLA H", time: 8, f: Exec::None(|c, _| c.h = c.sla(c.h)) }, // 0x24
+    OpCode { name: "SLA L", time: 8, f: Exec::None(|c, _| c.l = c.sla(c.l)) }, // 0x25
+    OpCode { name: "SLA (HL)", time: 16, f: Exec::None(|c, m| { let v = c.sla(m.rb(c.r_hl())); m.wb(c.r_hl(), v) }) }, // 0x26
+    OpCode { name: "SLA A", time: 8, f: Exec::None(|c, _| c.a = c.sla(c.a)) }, // 0x27
+    OpCode { name: "SRA B", time: 8, f: Exec::None(|c, _| c.b = c.sra(c.b)) }, // 0x28
+    OpCode { name: "SRA C", time: 8, f: Exec::None(|c, _| c.c = c.sra(c.c)) }, // 0x29
+    OpCode { name: "SRA D", time: 8, f: Exec::None(|c, _| c.d = c.sra(c.d)) }, // 0x2a
+    OpCode { name: "SRA E", time: 8, f: Exec::None(|c, _| c.e = c.sra(c.e)) }, // 0x2b
+    OpCode { name: "SRA H", time: 8, f: Exec::None(|c, _| c.h = c.sra(c.h)) }, // 0x2c
+    OpCode { name: "SRA L", time: 8, f: Exec::None(|c, _| c.l = c.sra(c.l)) }, // 0x2d
+    OpCode { name: "SRA (HL)", time: 16, f: Exec::None(|c, m| { let v = c.sra(m.rb(c.r_hl())); m.wb(c.r_hl(), v) }) }, // 0x2e
+    OpCode { name: "SRA A", time: 8, f: Exec::None(|c, _| c.a = c.sra(c.a)) }, // 0x2f
+    OpCode { name: "SWAP B", time: 8, f: Exec::None(|c, _| c.b = c.swap(c.b)) }, // 0x30
+    OpCode { name: "SWAP C", time: 8, f: Exec::None(|c, _| c.c = c.swap(c.c)) }, // 0x31
+    OpCode { name: "SWAP D", time: 8, f: Exec::None(|c, _| c.d = c.swap(c.d)) }, // 0x32
+    OpCode { name: "SWAP E", time: 8, f: Exec::None(|c, _| c.e = c.swap(c.e)) }, // 0x33
+    OpCode { name: "SWAP H", time: 8, f: Exec::None(|c, _| c.h = c.swap(c.h)) }, // 0x34
+    OpCode { name: "SWAP L", time: 8, f: Exec::None(|c, _| c.l = c.swap(c.l)) }, // 0x35
+    OpCode { name: "SWAP (HL)", time: 16, f: Exec::None(|c, m| { let v = c.swap(m.rb(c.r_hl())); m.wb(c.r_hl(), v) }) }, // 0x36
+    OpCode { name: "SWAP A", time: 8, f: Exec::None(|c, _| c.a = c.swap(c.a)) }, // 0x37
+    OpCode { name: "SRL B", time: 8, f: Exec::None(|c, _| c.b = c.srl(c.b)) }, // 0x38
+    OpCode { name: "SRL C", time: 8, f: Exec::None(|c, _| c.c = c.srl(c.c)) }, // 0x39
+    OpCode { name: "SRL D", time: 8, f: Exec::None(|c, _| c.d = c.srl(c.d)) }, // 0x3a
+    OpCode { name: "SRL E", time: 8, f: Exec::None(|c, _| c.e = c.srl(c.e)) }, // 0x3b
+    OpCode { name: "SRL H", time: 8, f: Exec::None(|c, _| c.h = c.srl(c.h)) }, // 0x3c
+    OpCode { name: "SRL L", time: 8, f: Exec::None(|c, _| c.l = c.srl(c.l)) }, // 0x3d
+    OpCode { name: "SRL (HL)", time: 16, f: Exec::None(|c, m| { let v = c.srl(m.rb(c.r_hl())); m.wb(c.r_hl(), v) }) }, // 0x3e
+    OpCode { name: "SRL A", time: 8, f: Exec::None(|c, _| c.a = c.srl(c.a)) }, // 0x3f
+    OpCode { name: "BIT 0,B", time: 8, f: Exec::None(|c, _| c.bit(0, c.b)) }, // 0x40
+    OpCode { name: "BIT 0,C", time: 8, f: Exec::None(|c, _| c.bit(0, c.c)) }, // 0x41
+    OpCode { name: "BIT 0,D", time: 8, f: Exec::None(|c, _| c.bit(0, c.d)) }, // 0x42
+    OpCode { name: "BIT 0,E", time: 8, f: Exec::None(|c, _| c.bit(0, c.e)) }, // 0x43
+    OpCode { name: "BIT 0,H", time: 8, f: Exec::None(|c, _| c.bit(0, c.h)) }, // 0x44
+    OpCode { name: "BIT 0,L", time: 8, f: Exec::None(|c, _| c.bit(0, c.l)) }, // 0x45
+    OpCode { name: "BIT 0,(HL)", time: 12, f: Exec::None(|c, m| c.bit(0, m.rb(c.r_hl()))) }, // 0x46
+    OpCode { name: "BIT 0,A", time: 8, f: Exec::None(|c, _| c.bit(0, c.a)) }, // 0x47
+    OpCode { name: "BIT 1,B", time: 8, f: Exec::None(|c, _| c.bit(1, c.b)) }, // 0x48
+    OpCode { name: "BIT 1,C", time: 8, f: Exec::None(|c, _| c.bit(1, c.c)) }, // 0x49
+    OpCode { name: "BIT 1,D", time: 8, f: Exec::None(|c, _| c.bit(1, c.d)) }, // 0x4a
+    OpCode { name: "BIT 1,E", time: 8, f: Exec::None(|c, _| c.bit(1, c.e)) }, // 0x4b
+    OpCode { name: "BIT 1,H", time: 8, f: Exec::None(|c, _| c.bit(1, c.h)) }, // 0x4c
+    OpCode { name: "BIT 1,L", time: 8, f: Exec::None(|c, _| c.bit(1, c.l)) }, // 0x4d
+    OpCode { name: "BIT 1,(HL)", time: 12, f: Exec::None(|c, m| c.bit(1, m.rb(c.r_hl()))) }, // 0x4e
+    OpCode { name: "BIT 1,A", time: 8, f: Exec::None(|c, _| c.bit(1, c.a)) }, // 0x4f
+    OpCode { name: "BIT 2,B", time: 8, f: Exec::None(|c, _| c.bit(2, c.b)) }, // 0x50
+    OpCode { name: "BIT 2,C", time: 8, f: Exec::None(|c, _| c.bit(2, c.c)) }, // 0x51
+    OpCode { name: "BIT 2,D", time: 8, f: Exec::None(|c, _| c.bit(2, c.d)) }, // 0x52
+    OpCode { name: "BIT 2,E", time: 8, f: Exec::None(|c, _| c.bit(2, c.e)) }, // 0x53
+    OpCode { name: "BIT 2,H", time: 8, f: Exec::None(|c, _| c.bit(2, c.h)) }, // 0x54
+    OpCode { name: "BIT 2,L", time: 8, f: Exec::None(|c, _| c.bit(2, c.l)) }, // 0x55
+    OpCode { name: "BIT 2,(HL)", time: 12, f: Exec::None(|c, m| c.bit(2, m.rb(c.r_hl()))) }, // 0x56
+    OpCode { name: "BIT 2,A", time: 8, f: Exec::None(|c, _| c.bit(2, c.a)) }, // 0x57
+    OpCode { name: "BIT 3,B", time: 8, f: Exec::None(|c, _| c.bit(3, c.b)) }, // 0x58
+    OpCode { name: "BIT 3,C", time: 8, f: Exec::None(|c, _| c.bit(3, c.c)) }, // 0x59
+    OpCode { name: "BIT 3,D", time: 8, f: Exec::None(|c, _| c.bit(3, c.d)) }, // 0x5a
+    OpCode { name: "BIT 3,E", time: 8, f: Exec::None(|c, _| c.bit(3, c.e)) }, // 0x5b
+    OpCode { name: "BIT 3,H", time: 8, f: Exec::None(|c, _| c.bit(3, c.h)) }, // 0x5c
+    OpCode { name: "BIT 3,L", time: 8, f: Exec::None(|c, _| c.bit(3, c.l)) }, // 0x5d
+    OpCode { name: "BIT 3,(HL)", time: 12, f: Exec::None(|c, m| c.bit(3, m.rb(c.r_hl()))) }, // 0x5e
+    OpCode { name: "BIT 3,A", time: 8, f: Exec::None(|c, _| c.bit(3, c.a)) }, // 0x5f
+    OpCode { name: "BIT 4,B", time: 8, f: Exec::None(|c, _| c.bit(4, c.b)) }, // 0x60
+    OpCode { name: "BIT 4,C", time: 8, f: Exec::None(|c, _| c.bit(4, c.c)) }, // 0x61
+    OpCode { name: "BIT 4,D", time: 8, f: Exec::None(|c, _| c.bit(4, c.d)) }, // 0x62
+    OpCode { name: "BIT 4,E", time: 8, f: Exec::None(|c, _| c.bit(4, c.e)) }, // 0x63
+    OpCode { name: "BIT 4,H", time: 8, f: Exec::None(|c, _| c.bit(4, c.h)) }, // 0x64
+    OpCode { name: "BIT 4,L", time: 8, f: Exec::None(|c, _| c.bit(4, c.l)) }, // 0x65
+    OpCode { name: "BIT 4,(HL)", time: 12, f: Exec::None(|c, m| c.bit(4, m.rb(c.r_hl()))) }, // 0x66
+    OpCode { name: "BIT 4,A", time: 8, f: Exec::None(|c, _| c.bit(4, c.a)) }, // 0x67
+    OpCode { name: "BIT 5,B", time: 8, f: Exec::None(|c, _| c.bit(5, c.b)) }, // 0x68
+    OpCode { name: "BIT 5,C", time: 8, f: Exec::None(|c, _| c.bit(5, c.c)) }, // 0x69
+    OpCode { name: "BIT 5,D", time: 8, f: Exec::None(|c, _| c.bit(5, c.d)) }, // 0x6a
+    OpCode { name: "BIT 5,E", time: 8, f: Exec::None(|c, _| c.bit(5, c.e)) }, // 0x6b
+    OpCode { name: "BIT 5,H", time: 8, f: Exec::None(|c, _| c.bit(5, c.h)) }, // 0x6c
+    OpCode { name: "BIT 5,L", time: 8, f: Exec::None(|c, _| c.bit(5, c.l)) }, // 0x6d
+    OpCode { name: "BIT 5,(HL)", time: 12, f: Exec::None(|c, m| c.bit(5, m.rb(c.r_hl()))) }, // 0x6e
+    OpCode { name: "BIT 5,A", time: 8, f: Exec::None(|c, _| c.bit(5, c.a)) }, // 0x6f
+    OpCode { name: "BIT 6,B", time: 8, f: Exec::None(|c, _| c.bit(6, c.b)) }, // 0x70
+    OpCode { name: "BIT 6,C", time: 8, f: Exec::None(|c, _| c.bit(6, c.c)) }, // 0x71
+    OpCode { name: "BIT 6,D", time: 8, f: Exec::None(|c, _| c.bit(6, c.d)) }, // 0x72
+    OpCode { name: "BIT 6,E", time: 8, f: Exec::None(|c, _| c.bit(6, c.e)) }, // 0x73
+    OpCode { name: "BIT 6,H", time: 8, f: Exec::None(|c, _| c.bit(6, c.h)) }, // 0x74
+    OpCode { name: "BIT 6,L", time: 8, f: Exec::None(|c, _| c.bit(6, c.l)) }, // 0x75
+    OpCode { name: "BIT 6,(HL)", time: 12, f: Exec::None(|c, m| c.bit(6, m.rb(c.r_hl()))) }, // 0x76
+    OpCode { name: "BIT 6,A", time: 8, f: Exec::None(|c, _| c.bit(6, c.a)) }, // 0x77
+    OpCode { name: "BIT 7,B", time: 8, f: Exec::None(|c, _| c.bit(7, c.b)) }, // 0x78
+    OpCode { name: "BIT 7,C", time: 8, f: Exec::None(|c, _| c.bit(7, c.c)) }, // 0x79
+    OpCode { name: "BIT 7,D", time: 8, f: Exec::None(|c, _| c.bit(7, c.d)) }, // 0x7a
+    OpCode { name: "BIT 7,E", time: 8, f: Exec::None(|c, _| c.bit(7, c.e)) }, // 0x7b
+    OpCode { name: "BIT 7,H", time: 8, f: Exec::None(|c, _| c.bit(7, c.h)) }, // 0x7c
+    OpCode { name: "BIT 7,L", time: 8, f: Exec::None(|c, _| c.bit(7, c.l)) }, // 0x7d
+    OpCode { name: "BIT 7,(HL)", time: 12, f: Exec::None(|c, m| c.bit(7, m.rb(c.r_hl()))) }, // 0x7e
+    OpCode { name: "BIT 7,A", time: 8, f: Exec::None(|c, _| c.bit(7, c.a)) }, // 0x7f
+    OpCode { name: "RES 0,B", time: 8, f: Exec::None(|c, _| c.b &= !(1 << 0)) }, // 0x80
+    OpCode { name: "RES 0,C", time: 8, f: Exec::None(|c, _| c.c &= !(1 << 0)) }, // 0x81
+    OpCode { name: "RES 0,D", time: 8, f: Exec::None(|c, _| c.d &= !(1 << 0)) }, // 0x82
+    OpCode { name: "RES 0,E", time: 8, f: Exec::None(|c, _| c.e &= !(1 << 0)) }, // 0x83
+    OpCode { name: "RES 0,H", time: 8, f: Exec::None(|c, _| c.h &= !(1 << 0)) }, // 0x84
+    OpCode { name: "RES 0,L", time: 8, f: Exec::None(|c, _| c.l &= !(1 << 0)) }, // 0x85
+    OpCode { name: "RES 0,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) & !(1 << 0))) }, // 0x86
+    OpCode { name: "RES 0,A", time: 8, f: Exec::None(|c, _| c.a &= !(1 << 0)) }, // 0x87
+    OpCode { name: "RES 1,B", time: 8, f: Exec::None(|c, _| c.b &= !(1 << 1)) }, // 0x88
+    OpCode { name: "RES 1,C", time: 8, f: Exec::None(|c, _| c.c &= !(1 << 1)) }, // 0x89
+    OpCode { name: "RES 1,D", time: 8, f: Exec::None(|c, _| c.d &= !(1 << 1)) }, // 0x8a
+    OpCode { name: "RES 1,E", time: 8, f: Exec::None(|c, _| c.e &= !(1 << 1)) }, // 0x8b
+    OpCode { name: "RES 1,H", time: 8, f: Exec::None(|c, _| c.h &= !(1 << 1)) }, // 0x8c
+    OpCode { name: "RES 1,L", time: 8, f: Exec::None(|c, _| c.l &= !(1 << 1)) }, // 0x8d
+    OpCode { name: "RES 1,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) & !(1 << 1))) }, // 0x8e
+    OpCode { name: "RES 1,A", time: 8, f: Exec::None(|c, _| c.a &= !(1 << 1)) }, // 0x8f
+    OpCode { name: "RES 2,B", time: 8, f: Exec::None(|c, _| c.b &= !(1 << 2)) }, // 0x90
+    OpCode { name: "RES 2,C", time: 8, f: Exec::None(|c, _| c.c &= !(1 << 2)) }, // 0x91
+    OpCode { name: "RES 2,D", time: 8, f: Exec::None(|c, _| c.d &= !(1 << 2)) }, // 0x92
+    OpCode { name: "RES 2,E", time: 8, f: Exec::None(|c, _| c.e &= !(1 << 2)) }, // 0x93
+    OpCode { name: "RES 2,H", time: 8, f: Exec::None(|c, _| c.h &= !(1 << 2)) }, // 0x94
+    OpCode { name: "RES 2,L", time: 8, f: Exec::None(|c, _| c.l &= !(1 << 2)) }, // 0x95
+    OpCode { name: "RES 2,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) & !(1 << 2))) }, // 0x96
+    OpCode { name: "RES 2,A", time: 8, f: Exec::None(|c, _| c.a &= !(1 << 2)) }, // 0x97
+    OpCode { name: "RES 3,B", time: 8, f: Exec::None(|c, _| c.b &= !(1 << 3)) }, // 0x98
+    OpCode { name: "RES 3,C", time: 8, f: Exec::None(|c, _| c.c &= !(1 << 3)) }, // 0x99
+    OpCode { name: "RES 3,D", time: 8, f: Exec::None(|c, _| c.d &= !(1 << 3)) }, // 0x9a
+    OpCode { name: "RES 3,E", time: 8, f: Exec::None(|c, _| c.e &= !(1 << 3)) }, // 0x9b
+    OpCode { name: "RES 3,H", time: 8, f: Exec::None(|c, _| c.h &= !(1 << 3)) }, // 0x9c
+    OpCode { name: "RES 3,L", time: 8, f: Exec::None(|c, _| c.l &= !(1 << 3)) }, // 0x9d
+    OpCode { name: "RES 3,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) & !(1 << 3))) }, // 0x9e
+    OpCode { name: "RES 3,A", time: 8, f: Exec::None(|c, _| c.a &= !(1 << 3)) }, // 0x9f
+    OpCode { name: "RES 4,B", time: 8, f: Exec::None(|c, _| c.b &= !(1 << 4)) }, // 0xa0
+    OpCode { name: "RES 4,C", time: 8, f: Exec::None(|c, _| c.c &= !(1 << 4)) }, // 0xa1
+    OpCode { name: "RES 4,D", time: 8, f: Exec::None(|c, _| c.d &= !(1 << 4)) }, // 0xa2
+    OpCode { name: "RES 4,E", time: 8, f: Exec::None(|c, _| c.e &= !(1 << 4)) }, // 0xa3
+    OpCode { name: "RES 4,H", time: 8, f: Exec::None(|c, _| c.h &= !(1 << 4)) }, // 0xa4
+    OpCode { name: "RES 4,L", time: 8, f: Exec::None(|c, _| c.l &= !(1 << 4)) }, // 0xa5
+    OpCode { name: "RES 4,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) & !(1 << 4))) }, // 0xa6
+    OpCode { name: "RES 4,A", time: 8, f: Exec::None(|c, _| c.a &= !(1 << 4)) }, // 0xa7
+    OpCode { name: "RES 5,B", time: 8, f: Exec::None(|c, _| c.b &= !(1 << 5)) }, // 0xa8
+    OpCode { name: "RES 5,C", time: 8, f: Exec::None(|c, _| c.c &= !(1 << 5)) }, // 0xa9
+    OpCode { name: "RES 5,D", time: 8, f: Exec::None(|c, _| c.d &= !(1 << 5)) }, // 0xaa
+    OpCode { name: "RES 5,E", time: 8, f: Exec::None(|c, _| c.e &= !(1 << 5)) }, // 0xab
+    OpCode { name: "RES 5,H", time: 8, f: Exec::None(|c, _| c.h &= !(1 << 5)) }, // 0xac
+    OpCode { name: "RES 5,L", time: 8, f: Exec::None(|c, _| c.l &= !(1 << 5)) }, // 0xad
+    OpCode { name: "RES 5,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) & !(1 << 5))) }, // 0xae
+    OpCode { name: "RES 5,A", time: 8, f: Exec::None(|c, _| c.a &= !(1 << 5)) }, // 0xaf
+    OpCode { name: "RES 6,B", time: 8, f: Exec::None(|c, _| c.b &= !(1 << 6)) }, // 0xb0
+    OpCode { name: "RES 6,C", time: 8, f: Exec::None(|c, _| c.c &= !(1 << 6)) }, // 0xb1
+    OpCode { name: "RES 6,D", time: 8, f: Exec::None(|c, _| c.d &= !(1 << 6)) }, // 0xb2
+    OpCode { name: "RES 6,E", time: 8, f: Exec::None(|c, _| c.e &= !(1 << 6)) }, // 0xb3
+    OpCode { name: "RES 6,H", time: 8, f: Exec::None(|c, _| c.h &= !(1 << 6)) }, // 0xb4
+    OpCode { name: "RES 6,L", time: 8, f: Exec::None(|c, _| c.l &= !(1 << 6)) }, // 0xb5
+    OpCode { name: "RES 6,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) & !(1 << 6))) }, // 0xb6
+    OpCode { name: "RES 6,A", time: 8, f: Exec::None(|c, _| c.a &= !(1 << 6)) }, // 0xb7
+    OpCode { name: "RES 7,B", time: 8, f: Exec::None(|c, _| c.b &= !(1 << 7)) }, // 0xb8
+    OpCode { name: "RES 7,C", time: 8, f: Exec::None(|c, _| c.c &= !(1 << 7)) }, // 0xb9
+    OpCode { name: "RES 7,D", time: 8, f: Exec::None(|c, _| c.d &= !(1 << 7)) }, // 0xba
+    OpCode { name: "RES 7,E", time: 8, f: Exec::None(|c, _| c.e &= !(1 << 7)) }, // 0xbb
+    OpCode { name: "RES 7,H", time: 8, f: Exec::None(|c, _| c.h &= !(1 << 7)) }, // 0xbc
+    OpCode { name: "RES 7,L", time: 8, f: Exec::None(|c, _| c.l &= !(1 << 7)) }, // 0xbd
+    OpCode { name: "RES 7,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) & !(1 << 7))) }, // 0xbe
+    OpCode { name: "RES 7,A", time: 8, f: Exec::None(|c, _| c.a &= !(1 << 7)) }, // 0xbf
+    OpCode { name: "SET 0,B", time: 8, f: Exec::None(|c, _| c.b |= 1 << 0) }, // 0xc0
+    OpCode { name: "SET 0,C", time: 8, f: Exec::None(|c, _| c.c |= 1 << 0) }, // 0xc1
+    OpCode { name: "SET 0,D", time: 8, f: Exec::None(|c, _| c.d |= 1 << 0) }, // 0xc2
+    OpCode { name: "SET 0,E", time: 8, f: Exec::None(|c, _| c.e |= 1 << 0) }, // 0xc3
+    OpCode { name: "SET 0,H", time: 8, f: Exec::None(|c, _| c.h |= 1 << 0) }, // 0xc4
+    OpCode { name: "SET 0,L", time: 8, f: Exec::None(|c, _| c.l |= 1 << 0) }, // 0xc5
+    OpCode { name: "SET 0,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) | (1 << 0))) }, // 0xc6
+    OpCode { name: "SET 0,A", time: 8, f: Exec::None(|c, _| c.a |= 1 << 0) }, // 0xc7
+    OpCode { name: "SET 1,B", time: 8, f: Exec::None(|c, _| c.b |= 1 << 1) }, // 0xc8
+    OpCode { name: "SET 1,C", time: 8, f: Exec::None(|c, _| c.c |= 1 << 1) }, // 0xc9
+    OpCode { name: "SET 1,D", time: 8, f: Exec::None(|c, _| c.d |= 1 << 1) }, // 0xca
+    OpCode { name: "SET 1,E", time: 8, f: Exec::None(|c, _| c.e |= 1 << 1) }, // 0xcb
+    OpCode { name: "SET 1,H", time: 8, f: Exec::None(|c, _| c.h |= 1 << 1) }, // 0xcc
+    OpCode { name: "SET 1,L", time: 8, f: Exec::None(|c, _| c.l |= 1 << 1) }, // 0xcd
+    OpCode { name: "SET 1,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) | (1 << 1))) }, // 0xce
+    OpCode { name: "SET 1,A", time: 8, f: Exec::None(|c, _| c.a |= 1 << 1) }, // 0xcf
+    OpCode { name: "SET 2,B", time: 8, f: Exec::None(|c, _| c.b |= 1 << 2) }, // 0xd0
+    OpCode { name: "SET 2,C", time: 8, f: Exec::None(|c, _| c.c |= 1 << 2) }, // 0xd1
+    OpCode { name: "SET 2,D", time: 8, f: Exec::None(|c, _| c.d |= 1 << 2) }, // 0xd2
+    OpCode { name: "SET 2,E", time: 8, f: Exec::None(|c, _| c.e |= 1 << 2) }, // 0xd3
+    OpCode { name: "SET 2,H", time: 8, f: Exec::None(|c, _| c.h |= 1 << 2) }, // 0xd4
+    OpCode { name: "SET 2,L", time: 8, f: Exec::None(|c, _| c.l |= 1 << 2) }, // 0xd5
+    OpCode { name: "SET 2,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) | (1 << 2))) }, // 0xd6
+    OpCode { name: "SET 2,A", time: 8, f: Exec::None(|c, _| c.a |= 1 << 2) }, // 0xd7
+    OpCode { name: "SET 3,B", time: 8, f: Exec::None(|c, _| c.b |= 1 << 3) }, // 0xd8
+    OpCode { name: "SET 3,C", time: 8, f: Exec::None(|c, _| c.c |= 1 << 3) }, // 0xd9
+    OpCode { name: "SET 3,D", time: 8, f: Exec::None(|c, _| c.d |= 1 << 3) }, // 0xda
+    OpCode { name: "SET 3,E", time: 8, f: Exec::None(|c, _| c.e |= 1 << 3) }, // 0xdb
+    OpCode { name: "SET 3,H", time: 8, f: Exec::None(|c, _| c.h |= 1 << 3) }, // 0xdc
+    OpCode { name: "SET 3,L", time: 8, f: Exec::None(|c, _| c.l |= 1 << 3) }, // 0xdd
+    OpCode { name: "SET 3,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) | (1 << 3))) }, // 0xde
+    OpCode { name: "SET 3,A", time: 8, f: Exec::None(|c, _| c.a |= 1 << 3) }, // 0xdf
+    OpCode { name: "SET 4,B", time: 8, f: Exec::None(|c, _| c.b |= 1 << 4) }, // 0xe0
+    OpCode { name: "SET 4,C", time: 8, f: Exec::None(|c, _| c.c |= 1 << 4) }, // 0xe1
+    OpCode { name: "SET 4,D", time: 8, f: Exec::None(|c, _| c.d |= 1 << 4) }, // 0xe2
+    OpCode { name: "SET 4,E", time: 8, f: Exec::None(|c, _| c.e |= 1 << 4) }, // 0xe3
+    OpCode { name: "SET 4,H", time: 8, f: Exec::None(|c, _| c.h |= 1 << 4) }, // 0xe4
+    OpCode { name: "SET 4,L", time: 8, f: Exec::None(|c, _| c.l |= 1 << 4) }, // 0xe5
+    OpCode { name: "SET 4,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) | (1 << 4))) }, // 0xe6
+    OpCode { name: "SET 4,A", time: 8, f: Exec::None(|c, _| c.a |= 1 << 4) }, // 0xe7
+    OpCode { name: "SET 5,B", time: 8, f: Exec::None(|c, _| c.b |= 1 << 5) }, // 0xe8
+    OpCode { name: "SET 5,C", time: 8, f: Exec::None(|c, _| c.c |= 1 << 5) }, // 0xe9
+    OpCode { name: "SET 5,D", time: 8, f: Exec::None(|c, _| c.d |= 1 << 5) }, // 0xea
+    OpCode { name: "SET 5,E", time: 8, f: Exec::None(|c, _| c.e |= 1 << 5) }, // 0xeb
+    OpCode { name: "SET 5,H", time: 8, f: Exec::None(|c, _| c.h |= 1 << 5) }, // 0xec
+    OpCode { name: "SET 5,L", time: 8, f: Exec::None(|c, _| c.l |= 1 << 5) }, // 0xed
+    OpCode { name: "SET 5,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) | (1 << 5))) }, // 0xee
+    OpCode { name: "SET 5,A", time: 8, f: Exec::None(|c, _| c.a |= 1 << 5) }, // 0xef
+    OpCode { name: "SET 6,B", time: 8, f: Exec::None(|c, _| c.b |= 1 << 6) }, // 0xf0
+    OpCode { name: "SET 6,C", time: 8, f: Exec::None(|c, _| c.c |= 1 << 6) }, // 0xf1
+    OpCode { name: "SET 6,D", time: 8, f: Exec::None(|c, _| c.d |= 1 << 6) }, // 0xf2
+    OpCode { name: "SET 6,E", time: 8, f: Exec::None(|c, _| c.e |= 1 << 6) }, // 0xf3
+    OpCode { name: "SET 6,H", time: 8, f: Exec::None(|c, _| c.h |= 1 << 6) }, // 0xf4
+    OpCode { name: "SET 6,L", time: 8, f: Exec::None(|c, _| c.l |= 1 << 6) }, // 0xf5
+    OpCode { name: "SET 6,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) | (1 << 6))) }, // 0xf6
+    OpCode { name: "SET 6,A", time: 8, f: Exec::None(|c, _| c.a |= 1 << 6) }, // 0xf7
+    OpCode { name: "SET 7,B", time: 8, f: Exec::None(|c, _| c.b |= 1 << 7) }, // 0xf8
+    OpCode { name: "SET 7,C", time: 8, f: Exec::None(|c, _| c.c |= 1 << 7) }, // 0xf9
+    OpCode { name: "SET 7,D", time: 8, f: Exec::None(|c, _| c.d |= 1 << 7) }, // 0xfa
+    OpCode { name: "SET 7,E", time: 8, f: Exec::None(|c, _| c.e |= 1 << 7) }, // 0xfb
+    OpCode { name: "SET 7,H", time: 8, f: Exec::None(|c, _| c.h |= 1 << 7) }, // 0xfc
+    OpCode { name: "SET 7,L", time: 8, f: Exec::None(|c, _| c.l |= 1 << 7) }, // 0xfd
+    OpCode { name: "SET 7,(HL)", time: 16, f: Exec::None(|c, m| m.wb(c.r_hl(), m.rb(c.r_hl()) | (1 << 7))) }, // 0xfe
+    OpCode { name: "SET 7,A", time: 8, f: Exec::None(|c, _| c.a |= 1 << 7) }, // 0xff
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::{Executed, Flags};
+
+    /// Encoded length of the unprefixed instructions, 0 for the illegal opcodes.
+    #[rustfmt::skip]
+    const LENGTHS: [u8; 256] = [
+        1, 3, 1, 1, 1, 1, 2, 1, 3, 1, 1, 1, 1, 1, 2, 1, // 0x00
+        2, 3, 1, 1, 1, 1, 2, 1, 2, 1, 1, 1, 1, 1, 2, 1, // 0x10
+        2, 3, 1, 1, 1, 1, 2, 1, 2, 1, 1, 1, 1, 1, 2, 1, // 0x20
+        2, 3, 1, 1, 1, 1, 2, 1, 2, 1, 1, 1, 1, 1, 2, 1, // 0x30
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x40
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x50
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x60
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x70
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x80
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x90
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0xa0
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0xb0
+        1, 1, 3, 3, 3, 1, 2, 1, 1, 1, 3, 1, 3, 3, 2, 1, // 0xc0
+        1, 1, 3, 0, 3, 1, 2, 1, 1, 1, 3, 0, 3, 0, 2, 1, // 0xd0
+        2, 1, 1, 0, 0, 1, 2, 1, 2, 1, 3, 0, 0, 0, 2, 1, // 0xe0
+        2, 1, 1, 1, 0, 1, 2, 1, 2, 1, 3, 1, 0, 0, 2, 1, // 0xf0
+    ];
 
-    #[inline(always)]
-    pub fn get_pair(self) -> [u8;2] {
-        match self {
-            None => panic!("Cannot unwrap a pair on a none value"),
-            Bytes::One(_) => panic!("Cannot unwrap pair on a one"),
-            Bytes::Pair(y) => y
+    /// Opcodes with a signed immediate: the relative jumps, ADD SP,r8 and LD HL,SP+r8.
+    const SIGNED: [u8; 7] = [0x18, 0x20, 0x28, 0x30, 0x38, 0xE8, 0xF8];
+
+    #[test]
+    fn operand_sizes() {
+        for (opcode, op_code) in OP_CODES.iter().enumerate() {
+            let length = LENGTHS[opcode];
+            if length == 0 {
+                assert!(matches!(op_code.f, Exec::Illegal), "{:#04x} {}", opcode, op_code.name);
+                continue;
+            }
+            assert_eq!(op_code.operand_size() + 1, length, "{:#04x} {}", opcode, op_code.name);
+            let signed = matches!(op_code.f, Exec::I8(_));
+            assert_eq!(signed, SIGNED.contains(&(opcode as u8)), "{:#04x} {}", opcode, op_code.name);
         }
     }
 
-    #[inline(always)]
-    pub fn get_pair_u16(self) -> u16 {
-        u16::from_le_bytes(self.get_pair())
+    #[test]
+    fn conditional_timings() {
+        // (opcode, flag tested, taken when the flag is set, cycles taken, cycles not taken)
+        let cases = [
+            (0x18, Flags::empty(), false, 12, 12),
+            (0x20, Flags::ZERO, false, 12, 8),
+            (0x28, Flags::ZERO, true, 12, 8),
+            (0x30, Flags::CARRY, false, 12, 8),
+            (0x38, Flags::CARRY, true, 12, 8),
+            (0xC3, Flags::empty(), false, 16, 16),
+            (0xC2, Flags::ZERO, false, 16, 12),
+            (0xCA, Flags::ZERO, true, 16, 12),
+            (0xD2, Flags::CARRY, false, 16, 12),
+            (0xDA, Flags::CARRY, true, 16, 12),
+            (0xCD, Flags::empty(), false, 24, 24),
+            (0xC4, Flags::ZERO, false, 24, 12),
+            (0xCC, Flags::ZERO, true, 24, 12),
+            (0xD4, Flags::CARRY, false, 24, 12),
+            (0xDC, Flags::CARRY, true, 24, 12),
+            (0xC9, Flags::empty(), false, 16, 16),
+            (0xC0, Flags::ZERO, false, 20, 8),
+            (0xC8, Flags::ZERO, true, 20, 8),
+            (0xD0, Flags::CARRY, false, 20, 8),
+            (0xD8, Flags::CARRY, true, 20, 8),
+        ];
+        for (opcode, flag, taken_when_set, taken, not_taken) in cases {
+            for set in [false, true] {
+                let mut mmu = MMU::default();
+                // The jumps and calls stay in the WRAM, the returns go to 0x0000
+                mmu.wram[..3].copy_from_slice(&[opcode, 0x0E, 0xC0]);
+                let f = if set { flag } else { Flags::empty() };
+                let mut cpu = Cpu { pc: 0xC000, sp: 0xDFF0, f, ..Cpu::default() };
+                let step = cpu.step(&mut mmu).unwrap();
+                assert!(matches!(step.executed, Executed::Instruction { .. }), "{:#04x}", opcode);
+                let jumped = flag.is_empty() || set == taken_when_set;
+                let cycles = if jumped { taken } else { not_taken };
+                let name = OP_CODES[opcode as usize].name;
+                assert_eq!(step.cycles, cycles, "{:#04x} {}, flags {:?}", opcode, name, f);
+                if !jumped {
+                    let next = 0xC000 + LENGTHS[opcode as usize] as u16;
+                    assert_eq!(cpu.pc, next, "{:#04x} {}, flags {:?}", opcode, name, f);
+                }
+            }
+        }
     }
-}
 
-#[derive(Clone)]
-pub struct OpCode {
-    pub name: &'static str,
-    pub operand_size: u8,
-    pub time: u16,
-    pub f: fn(&mut Cpu, &mut MMU, operands: Bytes) -> (),
+    #[test]
+    fn cb_timings() {
+        for (opcode, op_code) in CB_OP_CODES.iter().enumerate() {
+            // The prefixed instructions have no immediate
+            assert_eq!(op_code.operand_size(), 0, "CB {:#04x} {}", opcode, op_code.name);
+            // (HL) is the operand 6, BIT only reads it while the others write it back
+            let hl = opcode & 0x07 == 6;
+            assert_eq!(op_code.name.ends_with("(HL)"), hl, "CB {:#04x} {}", opcode, op_code.name);
+            let time = match opcode {
+                _ if !hl => 8,
+                0x40..=0x7F => 12,
+                _ => 16,
+            };
+            assert_eq!(op_code.time, time, "CB {:#04x} {}", opcode, op_code.name);
+        }
+    }
 }
-
-pub const OP_CODES: &[OpCode] = &[
-    OpCode { name: "Nop", operand_size: 0, time: 4, f: |_, _, _| () }, // 0x0
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "LD nn,SP", operand_size: 2, time: 10, f: |x, _, y| x.sp = y.unwrap() }, // 0x08
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "LDH (n), A", operand_size: 1, time: 6, f: |c, m, y| m.wb(0xFF00 + y.map(|x|x.get_one() as u16).unwrap(), c.a) }, // 0xe0
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "ADD HL,DE", operand_size: 0, time: 4, f: |x, _, _| x.add_hl_de() }, // 0x19
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "JR cc,n", operand_size: 1, time: 4, f: |x, _, y| x.jr(!Flags::ZERO, y as u8) }, // 0x20
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    // FIXME 22
-    OpCode { name: "HLI, A", operand_size: 0, time: 2, f: |x, _, _| x.w_hl(x.a as u16) }, // 0x22
-    OpCode { name: "INC HL", operand_size: 2, time: 4, f: |x, _, y| x.w_hl(x.r_hl() + y) }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "LD SP NN", operand_size: 2, time: 12, f: |c, _, v| c.ld_sp_nn(v) }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "Dec A", operand_size: 0, time: 2, f: |x, _, _| Cpu::dec(&mut x.a)}, // 0x3d
-    // FIXME not sure
-    OpCode { name: "LD A,#", operand_size: 1, time: 4, f: |x, _, y| x.a = y as u8 }, // 0x3e
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "SBC A,C", operand_size: 0, time: 4, f: |x, _, _|  x.sbc_a(x.c)  }, // 0x99
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "xor_n", operand_size: 1, time: 8, f: |c, _, v| c.xor_n(v) }, // 0xee
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    // FIXME LD nn, A
-    OpCode { name: "LD (nn),A", operand_size: 2, time: 8, f: |x, mem, _| mem.ww(x.pc, x.a as u16) }, // 0xea
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "LD SP,HL", operand_size: 0, time: 4, f: |x, _, _| { x.sp = x.r_hl() } }, // 0xf9
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "not implemented", operand_size: 2, time: 2, f: |x, m, y| { unimplemented!() } }, // 0x31
-    OpCode { name: "CPn", operand_size: 1, time: 8, f: |c, _, v| c.cp_n(v) }, // 0xfe
-    OpCode { name: "RST 38H", operand_size: 0, time: 16, f: |c, m, _| c.rst(0x38, m) }, // 0xff
-];
\ No newline at end of file
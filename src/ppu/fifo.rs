@@ -0,0 +1,265 @@
+use std::collections::VecDeque;
+
+use crate::ppu::{shade, Lcdc, Ppu, SCREEN_WIDTH, TILE_MAP_0, TILE_MAP_1};
+
+/// Dots lost at the start of mode 3 fetching the first tile, which is fetched again.
+const FIRST_FETCH_DOTS: u8 = 6;
+/// Dots taken by the sprite fetcher.
+const SPRITE_FETCH_DOTS: u8 = 6;
+/// Most dots waited for the background fetcher before a sprite fetch.
+const MAX_SPRITE_WAIT: u8 = 5;
+/// Step of the background fetcher where the tile is ready to be pushed, each of the 3 steps
+/// before (tile number, data low, data high) takes 2 dots.
+const PUSH_STEP: u8 = 6;
+
+/// Sprite pixel waiting in the sprite FIFO.
+#[derive(Clone, Copy, Debug, Default)]
+struct SpritePixel {
+    color: u8,
+    palette: u8,
+    behind_background: bool,
+    /// Priority key of the sprite, the lowest stays on top
+    priority: (u8, u8),
+}
+
+/// State of the pixel FIFO renderer during mode 3.
+#[derive(Debug, Default)]
+pub(super) struct Fifo {
+    background: VecDeque<u8>,
+    sprites: VecDeque<SpritePixel>,
+    /// Step of the background fetcher, in dots
+    step: u8,
+    /// Tile column fetched next, relative to the start of the line or the window
+    fetcher_x: u8,
+    tile_addr: usize,
+    tile_row: u8,
+    /// Fetching the window tiles
+    window: bool,
+    /// Pixels sent to the LCD
+    lx: u8,
+    /// Pixels still to drop from the first tile, SCX fine scroll
+    discard: u8,
+    /// Dots to wait before the fetcher starts
+    delay: u8,
+    /// Sprites of the line already fetched
+    fetched_sprites: usize,
+    /// Dots left before the next sprite of the line is fetched
+    sprite_stall: Option<u8>,
+    /// Background tile of the last sprite fetch, the wait for the fetcher is paid once per tile
+    wait_tile: Option<u8>,
+}
+
+impl Ppu {
+    /// Start mode 3 with empty FIFOs.
+    pub(super) fn start_fifo(&mut self) {
+        self.fifo = Fifo {
+            discard: self.scx % 8,
+            delay: FIRST_FETCH_DOTS,
+            ..Fifo::default()
+        };
+    }
+
+    /// Run one dot of mode 3, returns true once the 160 pixels of the line are out.
+    pub(super) fn fifo_dot(&mut self) -> bool {
+        if self.fifo.delay > 0 {
+            self.fifo.delay -= 1;
+            return false;
+        }
+
+        self.check_window();
+        if self.fetch_sprite() {
+            return false;
+        }
+        self.fetch_background();
+
+        let Some(color) = self.fifo.background.pop_front() else {
+            return false;
+        };
+        if self.fifo.discard > 0 {
+            self.fifo.discard -= 1;
+            return false;
+        }
+        let sprite = self.fifo.sprites.pop_front().unwrap_or_default();
+        let color = if self.lcdc.contains(Lcdc::BG_ENABLE) { color } else { 0 };
+        let sprite_visible = !sprite.behind_background || color == 0;
+        let pixel = if sprite.color != 0 && sprite_visible && self.lcdc.contains(Lcdc::OBJ_ENABLE) {
+            shade(sprite.palette, sprite.color)
+        } else {
+            shade(self.bgp, color)
+        };
+        self.framebuffer[self.ly as usize * SCREEN_WIDTH + self.fifo.lx as usize] = pixel;
+        self.fifo.lx += 1;
+
+        let done = self.fifo.lx as usize == SCREEN_WIDTH;
+        if done && self.fifo.window {
+            self.window_line += 1;
+        }
+        done
+    }
+
+    /// Switch the fetcher to the window when the pixel at WX - 7 is reached.
+    fn check_window(&mut self) {
+        let fifo = &mut self.fifo;
+        if fifo.window
+            || !self.lcdc.contains(Lcdc::WINDOW_ENABLE)
+            || !self.window_reached
+            || fifo.lx as u16 + 7 < self.wx as u16
+            || self.wx > 166
+        {
+            return;
+        }
+        fifo.window = true;
+        fifo.background.clear();
+        fifo.step = 0;
+        fifo.fetcher_x = 0;
+        // The part of the window left of the screen when WX < 7
+        fifo.discard = if fifo.lx == 0 { 7u8.saturating_sub(self.wx) } else { 0 };
+    }
+
+    /// Fetch the next sprite when the pixel it starts at is reached, the output is stalled by
+    /// the fetch and by the wait for the background fetcher to be done with the current tile:
+    /// 11 - min(5, pixels of the tile already out) dots for the first sprite on a tile.
+    /// Returns true while the output is stalled.
+    fn fetch_sprite(&mut self) -> bool {
+        let Some(&sprite) = self.line_sprites.get(self.fifo.fetched_sprites) else {
+            return false;
+        };
+        let x = sprite.x;
+        if !self.lcdc.contains(Lcdc::OBJ_ENABLE) || self.fifo.discard > 0 || x as u16 > self.fifo.lx as u16 + 8 {
+            return false;
+        }
+        let stall = match self.fifo.sprite_stall {
+            Some(stall) => stall,
+            None => {
+                let position = if self.fifo.window {
+                    self.fifo.lx.wrapping_add(7).wrapping_sub(self.wx)
+                } else {
+                    self.fifo.lx.wrapping_add(self.scx)
+                };
+                let tile = position / 8;
+                let wait = if self.fifo.wait_tile == Some(tile) {
+                    0
+                } else {
+                    MAX_SPRITE_WAIT.saturating_sub(position % 8)
+                };
+                self.fifo.wait_tile = Some(tile);
+                SPRITE_FETCH_DOTS + wait
+            }
+        };
+        if stall > 1 {
+            self.fifo.sprite_stall = Some(stall - 1);
+            return true;
+        }
+        self.fifo.sprite_stall = None;
+        self.fifo.fetched_sprites += 1;
+
+        let palette = sprite.palette(self);
+        let priority = self.sprite_priority(&sprite);
+        // The columns left of the screen are already gone
+        let skip = (self.fifo.lx as u16 + 8 - x as u16) as u8;
+        while self.fifo.sprites.len() < 8 {
+            self.fifo.sprites.push_back(SpritePixel::default());
+        }
+        for column in skip..8 {
+            let color = sprite.pixel(self, column);
+            // The pixels of the sprites fetched before stay on top unless this one has a higher
+            // priority, only possible on CGB
+            let slot = &mut self.fifo.sprites[(column - skip) as usize];
+            if slot.color == 0 || (color != 0 && priority < slot.priority) {
+                *slot = SpritePixel {
+                    color,
+                    palette,
+                    behind_background: sprite.behind_background(),
+                    priority,
+                };
+            }
+        }
+        true
+    }
+
+    /// Advance the background fetcher by one dot, it reads the registers when it needs them so
+    /// mid line writes take effect at the next tile.
+    fn fetch_background(&mut self) {
+        match self.fifo.step {
+            0 => {
+                let (map, x, y) = if self.fifo.window {
+                    let map = if self.lcdc.contains(Lcdc::WINDOW_MAP) { TILE_MAP_1 } else { TILE_MAP_0 };
+                    (map, self.fifo.fetcher_x, self.window_line)
+                } else {
+                    let map = if self.lcdc.contains(Lcdc::BG_MAP) { TILE_MAP_1 } else { TILE_MAP_0 };
+                    let x = (self.scx / 8).wrapping_add(self.fifo.fetcher_x) & 0x1F;
+                    (map, x, self.ly.wrapping_add(self.scy))
+                };
+                let tile = self.vram[map + (y as usize / 8) * 32 + x as usize];
+                self.fifo.tile_addr = self.tile_data_addr(tile);
+                self.fifo.tile_row = y % 8;
+                self.fifo.step += 1;
+            }
+            PUSH_STEP => {
+                // The tile waits until the FIFO is empty
+                if self.fifo.background.is_empty() {
+                    for x in 0..8 {
+                        let color = self.tile_pixel(self.fifo.tile_addr, x, self.fifo.tile_row);
+                        self.fifo.background.push_back(color);
+                    }
+                    self.fifo.fetcher_x = self.fifo.fetcher_x.wrapping_add(1);
+                    self.fifo.step = 0;
+                }
+            }
+            _ => self.fifo.step += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ppu::{Lcdc, Mode, Ppu, Renderer, LCDC_ADDR, OAM_SCAN_DOTS};
+
+    /// Dots of the mode 3 of the line 0, with the LCD turned on with `lcdc`.
+    fn drawing_dots(ppu: &mut Ppu, lcdc: Lcdc) -> u16 {
+        ppu.write(LCDC_ADDR, (lcdc | Lcdc::ENABLE).bits());
+        ppu.tick(OAM_SCAN_DOTS);
+        assert_eq!(ppu.mode, Mode::Drawing);
+        let mut dots = 0;
+        while ppu.mode == Mode::Drawing {
+            ppu.tick(1);
+            dots += 1;
+        }
+        dots
+    }
+
+    #[test]
+    fn mode_3_length() {
+        // (SCX, X of a sprite, WX, dots of mode 3)
+        let cases = [
+            (0, None, None, 172),
+            // The pixels of the first tile dropped for the fine scroll
+            (5, None, None, 177),
+            (8, None, None, 172),
+            (15, None, None, 179),
+            // A sprite at the left edge waits for the whole first tile: 6 + 5 dots
+            (0, Some(8), None, 183),
+            // Once 5 pixels of the tile are out there is no wait left
+            (0, Some(13), None, 178),
+            (0, Some(16), None, 183),
+            // The window restarts the background fetcher
+            (0, None, Some(87), 178),
+            // Past the right edge the window isn't shown
+            (0, None, Some(167), 172),
+        ];
+        for (scx, sprite, wx, dots) in cases {
+            let mut ppu = Ppu::new(Renderer::PixelFifo);
+            ppu.scx = scx;
+            let mut lcdc = Lcdc::BG_ENABLE;
+            if let Some(x) = sprite {
+                ppu.oam[..4].copy_from_slice(&[16, x, 0, 0]);
+                lcdc |= Lcdc::OBJ_ENABLE;
+            }
+            if let Some(wx) = wx {
+                ppu.wx = wx;
+                lcdc |= Lcdc::WINDOW_ENABLE;
+            }
+            assert_eq!(drawing_dots(&mut ppu, lcdc), dots, "SCX {}, sprite {:?}, WX {:?}", scx, sprite, wx);
+        }
+    }
+}
@@ -0,0 +1,456 @@
+//! LCD controller: the LCDC, STAT, scroll, LY/LYC, palette and window registers (0xFF40-0xFF4B),
+//! the VRAM and OAM, and the renderer drawing each line into the framebuffer.
+
+use bitflags::bitflags;
+
+use crate::interrupts::Interrupt;
+
+mod fifo;
+mod scanline;
+mod sprites;
+
+use fifo::Fifo;
+use sprites::Sprite;
+
+pub const LCDC_ADDR: u16 = 0xFF40;
+pub const STAT_ADDR: u16 = 0xFF41;
+pub const SCY_ADDR: u16 = 0xFF42;
+pub const SCX_ADDR: u16 = 0xFF43;
+pub const LY_ADDR: u16 = 0xFF44;
+pub const LYC_ADDR: u16 = 0xFF45;
+pub const BGP_ADDR: u16 = 0xFF47;
+pub const OBP0_ADDR: u16 = 0xFF48;
+pub const OBP1_ADDR: u16 = 0xFF49;
+pub const WY_ADDR: u16 = 0xFF4A;
+pub const WX_ADDR: u16 = 0xFF4B;
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+pub const VRAM_SIZE: usize = 0x2000;
+pub const OAM_SIZE: usize = 0xA0;
+
+const DOTS_PER_LINE: u16 = 456;
+const OAM_SCAN_DOTS: u16 = 80;
+/// Length of mode 3 without the penalties of the scroll, window and sprites.
+const DRAWING_DOTS: u16 = 172;
+const LINES_PER_FRAME: u8 = 154;
+
+/// Offsets of the tile maps and of the signed tile data block in the VRAM.
+const TILE_MAP_0: usize = 0x1800;
+const TILE_MAP_1: usize = 0x1C00;
+const SIGNED_TILE_DATA: usize = 0x1000;
+
+bitflags! {
+    /// LCD control register (0xFF40).
+    pub struct Lcdc: u8 {
+        const ENABLE = 0x80;
+        /// Window tile map at 0x9C00 instead of 0x9800
+        const WINDOW_MAP = 0x40;
+        const WINDOW_ENABLE = 0x20;
+        /// Background and window tiles at 0x8000 with unsigned indexes instead of 0x9000 signed
+        const TILE_DATA = 0x10;
+        /// Background tile map at 0x9C00 instead of 0x9800
+        const BG_MAP = 0x08;
+        /// 8x16 sprites
+        const OBJ_SIZE = 0x04;
+        const OBJ_ENABLE = 0x02;
+        /// On DMG clearing it blanks the background and the window
+        const BG_ENABLE = 0x01;
+    }
+}
+
+bitflags! {
+    /// Interrupt sources selected in STAT (0xFF41).
+    pub struct StatSource: u8 {
+        const LYC = 0x40;
+        const OAM_SCAN = 0x20;
+        const VBLANK = 0x10;
+        const HBLANK = 0x08;
+    }
+}
+
+/// How the lines are drawn, selected when the emulator is created.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Renderer {
+    /// Each line is drawn at once at the end of mode 3 and mode 3 has a fixed length, fast but
+    /// the register writes during mode 3 only apply to the next line
+    #[default]
+    Scanline,
+    /// The pixels are fetched and output one dot at a time like the hardware does, mode 3
+    /// length depends on the scroll, the window and the sprites and mid line writes apply at once
+    PixelFifo,
+}
+
+/// PPU mode, as reported in the lower bits of STAT.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    HBlank = 0,
+    VBlank = 1,
+    OamScan = 2,
+    Drawing = 3,
+}
+
+/// The picture processing unit.
+pub struct Ppu {
+    pub renderer: Renderer,
+    fifo: Fifo,
+    pub vram: [u8; VRAM_SIZE],
+    pub oam: [u8; OAM_SIZE],
+    pub lcdc: Lcdc,
+    pub stat: StatSource,
+    pub scy: u8,
+    pub scx: u8,
+    pub ly: u8,
+    pub lyc: u8,
+    pub bgp: u8,
+    pub obp0: u8,
+    pub obp1: u8,
+    pub wy: u8,
+    pub wx: u8,
+    pub mode: Mode,
+    /// Running a CGB cartridge, the sprites priority follows the OAM order instead of X
+    pub cgb_mode: bool,
+    /// Sprites selected by the OAM scan of the line
+    line_sprites: Vec<Sprite>,
+    /// Dot of the current line, 0-455
+    dot: u16,
+    /// Line of the window to draw next, only incremented on the lines showing the window
+    window_line: u8,
+    /// WY matched LY during this frame, the window can be shown
+    window_reached: bool,
+    /// OR of the enabled STAT sources, the interrupt is requested on its rising edge
+    stat_line: bool,
+    /// Shades of the pixels, 0 is white and 3 is black
+    framebuffer: Box<[u8; SCREEN_WIDTH * SCREEN_HEIGHT]>,
+    /// Number of frames completed, incremented when VBlank starts
+    pub frames: u64,
+}
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Self::new(Renderer::default())
+    }
+}
+
+impl Ppu {
+    pub fn new(renderer: Renderer) -> Self {
+        Ppu {
+            renderer,
+            fifo: Fifo::default(),
+            vram: [0; VRAM_SIZE],
+            oam: [0; OAM_SIZE],
+            lcdc: Lcdc::empty(),
+            stat: StatSource::empty(),
+            scy: 0,
+            scx: 0,
+            ly: 0,
+            lyc: 0,
+            bgp: 0,
+            obp0: 0,
+            obp1: 0,
+            wy: 0,
+            wx: 0,
+            mode: Mode::HBlank,
+            cgb_mode: false,
+            line_sprites: Vec::new(),
+            dot: 0,
+            window_line: 0,
+            window_reached: false,
+            stat_line: false,
+            framebuffer: Box::new([0; SCREEN_WIDTH * SCREEN_HEIGHT]),
+            frames: 0,
+        }
+    }
+
+    /// The last frame drawn, one shade per pixel row by row: 0 is white and 3 is black.
+    pub fn frame(&self) -> &[u8] {
+        &self.framebuffer[..]
+    }
+
+    /// The PPU reads the OAM during the OAM scan and the drawing, the cpu can't access it.
+    pub fn oam_blocked(&self) -> bool {
+        self.lcdc.contains(Lcdc::ENABLE) && matches!(self.mode, Mode::OamScan | Mode::Drawing)
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            LCDC_ADDR => self.lcdc.bits(),
+            STAT_ADDR => {
+                let coincidence = (self.ly == self.lyc) as u8;
+                0x80 | self.stat.bits() | coincidence << 2 | self.mode as u8
+            }
+            SCY_ADDR => self.scy,
+            SCX_ADDR => self.scx,
+            LY_ADDR => self.ly,
+            LYC_ADDR => self.lyc,
+            BGP_ADDR => self.bgp,
+            OBP0_ADDR => self.obp0,
+            OBP1_ADDR => self.obp1,
+            WY_ADDR => self.wy,
+            WX_ADDR => self.wx,
+            _ => 0xFF,
+        }
+    }
+
+    /// Returns the interrupts requested by the write.
+    pub fn write(&mut self, addr: u16, val: u8) -> Interrupt {
+        match addr {
+            LCDC_ADDR => {
+                let lcdc = Lcdc::from_bits_truncate(val);
+                if self.lcdc.contains(Lcdc::ENABLE) && !lcdc.contains(Lcdc::ENABLE) {
+                    self.turn_off();
+                } else if !self.lcdc.contains(Lcdc::ENABLE) && lcdc.contains(Lcdc::ENABLE) {
+                    self.mode = Mode::OamScan;
+                    self.window_reached = self.wy == 0;
+                }
+                self.lcdc = lcdc;
+            }
+            // Only the interrupt sources are writable
+            STAT_ADDR => self.stat = StatSource::from_bits_truncate(val),
+            SCY_ADDR => self.scy = val,
+            SCX_ADDR => self.scx = val,
+            // LY is read only
+            LY_ADDR => {}
+            LYC_ADDR => self.lyc = val,
+            BGP_ADDR => self.bgp = val,
+            OBP0_ADDR => self.obp0 = val,
+            OBP1_ADDR => self.obp1 = val,
+            WY_ADDR => self.wy = val,
+            WX_ADDR => self.wx = val,
+            _ => {}
+        }
+        self.update_stat_line()
+    }
+
+    /// Advance by `cycles` dots, returns the interrupts requested.
+    pub fn tick(&mut self, mut cycles: u16) -> Interrupt {
+        let mut interrupts = Interrupt::empty();
+        if !self.lcdc.contains(Lcdc::ENABLE) {
+            return interrupts;
+        }
+        while cycles > 0 {
+            if self.mode == Mode::Drawing && self.renderer == Renderer::PixelFifo {
+                // The end of mode 3 is only known once the last pixel is out
+                cycles -= 1;
+                self.dot += 1;
+                if self.fifo_dot() {
+                    interrupts |= self.next_mode();
+                }
+                continue;
+            }
+            let end = match self.mode {
+                Mode::OamScan => OAM_SCAN_DOTS,
+                Mode::Drawing => OAM_SCAN_DOTS + DRAWING_DOTS,
+                Mode::HBlank | Mode::VBlank => DOTS_PER_LINE,
+            };
+            // A long mode 3 of the pixel FIFO can end past the usual end of the line
+            let elapsed = cycles.min(end.saturating_sub(self.dot));
+            self.dot += elapsed;
+            cycles -= elapsed;
+            if self.dot >= end {
+                interrupts |= self.next_mode();
+            }
+        }
+        interrupts
+    }
+
+    fn next_mode(&mut self) -> Interrupt {
+        let mut interrupts = Interrupt::empty();
+        match self.mode {
+            Mode::OamScan => {
+                self.mode = Mode::Drawing;
+                self.scan_oam();
+                if self.renderer == Renderer::PixelFifo {
+                    self.start_fifo();
+                }
+            }
+            Mode::Drawing => {
+                if self.renderer == Renderer::Scanline {
+                    self.render_line();
+                }
+                self.mode = Mode::HBlank;
+            }
+            Mode::HBlank => {
+                self.dot = 0;
+                self.ly += 1;
+                if self.ly as usize == SCREEN_HEIGHT {
+                    self.mode = Mode::VBlank;
+                    self.frames += 1;
+                    interrupts |= Interrupt::VBLANK;
+                } else {
+                    self.start_line();
+                }
+            }
+            Mode::VBlank => {
+                self.dot = 0;
+                self.ly += 1;
+                if self.ly == LINES_PER_FRAME {
+                    self.ly = 0;
+                    self.window_line = 0;
+                    self.window_reached = false;
+                    self.start_line();
+                }
+            }
+        }
+        interrupts | self.update_stat_line()
+    }
+
+    fn start_line(&mut self) {
+        self.mode = Mode::OamScan;
+        if self.ly == self.wy {
+            self.window_reached = true;
+        }
+    }
+
+    /// The LCD is off until LCDC bit 7 is set again, LY stays at 0 in mode 0 and the screen is
+    /// blank.
+    fn turn_off(&mut self) {
+        self.ly = 0;
+        self.dot = 0;
+        self.mode = Mode::HBlank;
+        self.window_line = 0;
+        self.framebuffer.fill(0);
+    }
+
+    /// Recompute the STAT interrupt line, returns the STAT interrupt on its rising edge.
+    fn update_stat_line(&mut self) -> Interrupt {
+        let enabled = self.lcdc.contains(Lcdc::ENABLE);
+        let source = match self.mode {
+            Mode::HBlank => StatSource::HBLANK,
+            Mode::VBlank => StatSource::VBLANK,
+            Mode::OamScan => StatSource::OAM_SCAN,
+            Mode::Drawing => StatSource::empty(),
+        };
+        let line = enabled
+            && (self.stat.intersects(source) || (self.stat.contains(StatSource::LYC) && self.ly == self.lyc));
+        let rising = line && !self.stat_line;
+        self.stat_line = line;
+        if rising {
+            Interrupt::STAT
+        } else {
+            Interrupt::empty()
+        }
+    }
+
+    /// Color index 0-3 of the pixel at (`x`, `y`) of the 256x256 tile map.
+    pub(super) fn map_pixel(&self, map: usize, x: u8, y: u8) -> u8 {
+        let tile = self.vram[map + (y as usize / 8) * 32 + x as usize / 8];
+        self.tile_pixel(self.tile_data_addr(tile), x % 8, y % 8)
+    }
+
+    /// Offset in the VRAM of a background or window tile.
+    pub(super) fn tile_data_addr(&self, tile: u8) -> usize {
+        if self.lcdc.contains(Lcdc::TILE_DATA) {
+            tile as usize * 16
+        } else {
+            (SIGNED_TILE_DATA as isize + tile as i8 as isize * 16) as usize
+        }
+    }
+
+    /// Color index 0-3 of the pixel of a tile, each row is 2 bytes holding the low and high bits.
+    pub(super) fn tile_pixel(&self, tile_addr: usize, x: u8, y: u8) -> u8 {
+        let row = tile_addr + y as usize * 2;
+        let bit = 7 - x;
+        let low = (self.vram[row] >> bit) & 1;
+        let high = (self.vram[row + 1] >> bit) & 1;
+        high << 1 | low
+    }
+}
+
+/// Shade of the color index through a DMG palette register.
+#[inline]
+pub(super) fn shade(palette: u8, color: u8) -> u8 {
+    (palette >> (color * 2)) & 0x03
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_DOTS: u32 = DOTS_PER_LINE as u32 * LINES_PER_FRAME as u32;
+
+    /// Turn the LCD on and run `dots`, returns the number of STAT interrupts requested.
+    fn stat_interrupts(ppu: &mut Ppu, dots: u32) -> u32 {
+        let mut count = ppu.write(LCDC_ADDR, Lcdc::ENABLE.bits()).contains(Interrupt::STAT) as u32;
+        for _ in 0..dots / 4 {
+            count += ppu.tick(4).contains(Interrupt::STAT) as u32;
+        }
+        count
+    }
+
+    #[test]
+    fn stat_sources() {
+        // (STAT sources, LYC, STAT interrupts in a frame and the start of the next)
+        let cases = [
+            (StatSource::empty(), 0, 0),
+            (StatSource::HBLANK, 0xFF, 144),
+            (StatSource::VBLANK, 0xFF, 1),
+            (StatSource::OAM_SCAN, 0xFF, 145),
+            (StatSource::LYC, 5, 1),
+            (StatSource::LYC, 150, 1),
+            // The sources share the line, a mode following another selected one doesn't make a
+            // rising edge
+            (StatSource::HBLANK | StatSource::OAM_SCAN, 0xFF, 146),
+            (StatSource::HBLANK | StatSource::VBLANK, 0xFF, 144),
+            (StatSource::LYC | StatSource::OAM_SCAN, 0, 144),
+            (StatSource::all(), 0xFF, 145),
+        ];
+        for (sources, lyc, interrupts) in cases {
+            let mut ppu = Ppu::default();
+            ppu.write(STAT_ADDR, sources.bits());
+            ppu.write(LYC_ADDR, lyc);
+            assert_eq!(stat_interrupts(&mut ppu, FRAME_DOTS), interrupts, "{:?}, LYC {}", sources, lyc);
+        }
+    }
+
+    #[test]
+    fn lyc_coincidence() {
+        let mut ppu = Ppu::default();
+        ppu.write(STAT_ADDR, StatSource::LYC.bits());
+        ppu.write(LYC_ADDR, 2);
+        // Requested when LY reaches LYC
+        assert_eq!(stat_interrupts(&mut ppu, 2 * DOTS_PER_LINE as u32 - 4), 0);
+        assert_eq!(ppu.read(STAT_ADDR) & 0x04, 0);
+        assert_eq!(ppu.tick(4), Interrupt::STAT);
+        assert_eq!(ppu.ly, 2);
+        assert_eq!(ppu.read(STAT_ADDR) & 0x04, 0x04);
+        // Writing LYC to the current line is a rising edge too
+        ppu.write(LYC_ADDR, 3);
+        assert_eq!(ppu.write(LYC_ADDR, 2), Interrupt::STAT);
+        // Not requested again while LY stays on LYC
+        assert_eq!(ppu.tick(DOTS_PER_LINE - 4), Interrupt::empty());
+    }
+
+    #[test]
+    fn window_line_counter() {
+        // WX of each line, the window is shown from line 0 but is moved off screen on some lines
+        let lines = [7, 7, 200, 7, 167, 50, 7];
+        for renderer in [Renderer::Scanline, Renderer::PixelFifo] {
+            let mut ppu = Ppu::new(renderer);
+            ppu.write(LCDC_ADDR, (Lcdc::ENABLE | Lcdc::BG_ENABLE | Lcdc::WINDOW_ENABLE).bits());
+            let mut window_line = 0;
+            for (ly, wx) in lines.iter().copied().enumerate() {
+                ppu.wx = wx;
+                ppu.tick(DOTS_PER_LINE);
+                if wx <= 166 {
+                    window_line += 1;
+                }
+                assert_eq!(ppu.window_line, window_line, "{:?}, line {} with WX {}", renderer, ly, wx);
+            }
+        }
+    }
+
+    #[test]
+    fn window_starts_at_wy() {
+        for renderer in [Renderer::Scanline, Renderer::PixelFifo] {
+            let mut ppu = Ppu::new(renderer);
+            ppu.wy = 3;
+            ppu.wx = 7;
+            ppu.write(LCDC_ADDR, (Lcdc::ENABLE | Lcdc::BG_ENABLE | Lcdc::WINDOW_ENABLE).bits());
+            ppu.tick(3 * DOTS_PER_LINE);
+            assert_eq!(ppu.window_line, 0, "{:?}", renderer);
+            ppu.tick(2 * DOTS_PER_LINE);
+            assert_eq!(ppu.window_line, 2, "{:?}", renderer);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::ppu::{shade, Lcdc, Ppu, SCREEN_WIDTH, TILE_MAP_0, TILE_MAP_1};
+
+impl Ppu {
+    /// Draw the whole line at once with the registers at the end of mode 3.
+    pub(super) fn render_line(&mut self) {
+        let ly = self.ly;
+        let mut line = [0u8; SCREEN_WIDTH];
+
+        if self.lcdc.contains(Lcdc::BG_ENABLE) {
+            let map = if self.lcdc.contains(Lcdc::BG_MAP) { TILE_MAP_1 } else { TILE_MAP_0 };
+            let y = ly.wrapping_add(self.scy);
+            for (x, pixel) in line.iter_mut().enumerate() {
+                *pixel = self.map_pixel(map, (x as u8).wrapping_add(self.scx), y);
+            }
+
+            // WX is the position of the window plus 7
+            if self.lcdc.contains(Lcdc::WINDOW_ENABLE) && self.window_reached && self.wx <= 166 {
+                let map = if self.lcdc.contains(Lcdc::WINDOW_MAP) { TILE_MAP_1 } else { TILE_MAP_0 };
+                let start = self.wx as isize - 7;
+                for (x, pixel) in line.iter_mut().enumerate().skip(start.max(0) as usize) {
+                    *pixel = self.map_pixel(map, (x as isize - start) as u8, self.window_line);
+                }
+                self.window_line += 1;
+            }
+        }
+        for (x, pixel) in line.iter_mut().enumerate() {
+            let background = *pixel;
+            *pixel = shade(self.bgp, background);
+            if !self.lcdc.contains(Lcdc::OBJ_ENABLE) {
+                continue;
+            }
+            // Only the sprite on top is checked against the background, the ones below are
+            // hidden even where it is behind the background
+            let sprite = self
+                .line_sprites
+                .iter()
+                .filter_map(|sprite| Some((sprite, sprite.pixel_at(self, x as u8)?)))
+                .min_by_key(|(sprite, _)| self.sprite_priority(sprite));
+            if let Some((sprite, color)) = sprite {
+                if !sprite.behind_background() || background == 0 {
+                    *pixel = shade(sprite.palette(self), color);
+                }
+            }
+        }
+
+        let start = ly as usize * SCREEN_WIDTH;
+        self.framebuffer[start..start + SCREEN_WIDTH].copy_from_slice(&line);
+    }
+}
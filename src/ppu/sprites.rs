@@ -0,0 +1,203 @@
+use crate::ppu::{Lcdc, Ppu};
+
+/// Most sprites selected on a line, the next ones in the OAM are not drawn.
+const MAX_LINE_SPRITES: usize = 10;
+
+/// A sprite selected by the OAM scan for the current line.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Sprite {
+    /// Index of the entry in the OAM
+    pub index: u8,
+    /// Position of the right edge plus 1, the sprite starts at X - 8 on the screen
+    pub x: u8,
+    /// Offset in the VRAM of the row of the tile shown on the line, flips applied
+    pub row_addr: usize,
+    pub attributes: u8,
+}
+
+impl Sprite {
+    /// Color index 0-3 of the column of the sprite, flips applied.
+    pub fn pixel(&self, ppu: &Ppu, column: u8) -> u8 {
+        let column = if self.attributes & 0x20 != 0 { 7 - column } else { column };
+        ppu.tile_pixel(self.row_addr, column, 0)
+    }
+
+    /// Color index of the sprite at `screen_x`, None when the sprite doesn't cover it or the
+    /// pixel is transparent.
+    pub fn pixel_at(&self, ppu: &Ppu, screen_x: u8) -> Option<u8> {
+        let column = (screen_x as u16 + 8).checked_sub(self.x as u16).filter(|&c| c < 8)?;
+        Some(self.pixel(ppu, column as u8)).filter(|&color| color != 0)
+    }
+
+    /// OBP1 or OBP0, selected by attribute bit 4.
+    pub fn palette(&self, ppu: &Ppu) -> u8 {
+        if self.attributes & 0x10 != 0 { ppu.obp1 } else { ppu.obp0 }
+    }
+
+    /// Attribute bit 7, the background colors 1-3 are drawn over the sprite.
+    pub fn behind_background(&self) -> bool {
+        self.attributes & 0x80 != 0
+    }
+}
+
+impl Ppu {
+    /// Select the first 10 sprites of the OAM on the line, sorted by X like the fetcher reads
+    /// them.
+    pub(super) fn scan_oam(&mut self) {
+        let height = if self.lcdc.contains(Lcdc::OBJ_SIZE) { 16 } else { 8 };
+        let mut sprites: Vec<Sprite> = self
+            .oam
+            .chunks_exact(4)
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let (y, x, tile, attributes) = (entry[0], entry[1], entry[2], entry[3]);
+                // Y is the position of the bottom edge plus 1 of a 8x8 sprite, the sprite starts at
+                // Y - 16 on the screen
+                let row = self.ly as u16 + 16;
+                let row = row.checked_sub(y as u16).filter(|&row| row < height)? as u8;
+                let row = if attributes & 0x40 != 0 { height as u8 - 1 - row } else { row };
+                // The lower bit of the tile is ignored by 8x16 sprites, the row picks the tile
+                let tile = if height == 16 { tile & 0xFE } else { tile };
+                Some(Sprite {
+                    index: index as u8,
+                    x,
+                    row_addr: tile as usize * 16 + row as usize * 2,
+                    attributes,
+                })
+            })
+            .take(MAX_LINE_SPRITES)
+            .collect();
+        // Stable, the sprites with the same X are fetched in OAM order
+        sprites.sort_by_key(|sprite| sprite.x);
+        self.line_sprites = sprites;
+    }
+
+    /// Key of the sprite in the priority between the sprites, the lowest is drawn on top: on DMG
+    /// the lowest X then the lowest OAM index, on CGB the lowest OAM index.
+    pub(super) fn sprite_priority(&self, sprite: &Sprite) -> (u8, u8) {
+        if self.cgb_mode {
+            (0, sprite.index)
+        } else {
+            (sprite.x, sprite.index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ppu::{Renderer, DOTS_PER_LINE, LCDC_ADDR, SCREEN_WIDTH};
+
+    /// Identity palette, the color index is the shade.
+    const PALETTE: u8 = 0xE4;
+
+    fn set_sprite(ppu: &mut Ppu, index: usize, y: u8, x: u8, tile: u8, attributes: u8) {
+        ppu.oam[index * 4..index * 4 + 4].copy_from_slice(&[y, x, tile, attributes]);
+    }
+
+    /// Fill the tile at `addr` in the VRAM with the color index.
+    fn fill_tile(ppu: &mut Ppu, addr: usize, color: u8) {
+        for row in 0..8 {
+            ppu.vram[addr + row * 2] = if color & 1 != 0 { 0xFF } else { 0x00 };
+            ppu.vram[addr + row * 2 + 1] = if color & 2 != 0 { 0xFF } else { 0x00 };
+        }
+    }
+
+    /// Turn the LCD on and draw the line 0, returns its shades.
+    fn draw_first_line(ppu: &mut Ppu, lcdc: Lcdc) -> Vec<u8> {
+        ppu.write(LCDC_ADDR, (lcdc | Lcdc::ENABLE).bits());
+        ppu.tick(DOTS_PER_LINE);
+        ppu.frame()[..SCREEN_WIDTH].to_vec()
+    }
+
+    #[test]
+    fn ten_sprites_per_line_in_oam_order() {
+        let mut ppu = Ppu::default();
+        // The last sprites of the OAM are the leftmost, they are dropped anyway
+        for index in 0..12 {
+            set_sprite(&mut ppu, index, 16, 160 - index as u8 * 8, 0, 0);
+        }
+        // Not on the line, doesn't count
+        set_sprite(&mut ppu, 12, 40, 8, 0, 0);
+        ppu.scan_oam();
+        let indexes: Vec<u8> = ppu.line_sprites.iter().map(|sprite| sprite.index).collect();
+        assert_eq!(indexes, [9, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn sprite_rows() {
+        // (8x16, Y flip, LY, tile, offset of the row in the VRAM)
+        let cases = [
+            (false, false, 3, 0x05, 0x50 + 6),
+            (false, true, 3, 0x05, 0x50 + 8),
+            // The tile of a 8x16 sprite ignores bit 0, the row selects the upper or lower tile
+            (true, false, 0, 0x05, 0x40),
+            (true, false, 9, 0x05, 0x50 + 2),
+            (true, false, 9, 0x04, 0x50 + 2),
+            // The flip swaps the two tiles too
+            (true, true, 0, 0x05, 0x50 + 14),
+            (true, true, 15, 0x05, 0x40),
+            (true, true, 9, 0x04, 0x40 + 12),
+        ];
+        for (tall, flip, ly, tile, row_addr) in cases {
+            let mut ppu = Ppu::default();
+            ppu.lcdc.set(Lcdc::OBJ_SIZE, tall);
+            ppu.ly = ly;
+            set_sprite(&mut ppu, 0, 16, 8, tile, if flip { 0x40 } else { 0 });
+            ppu.scan_oam();
+            let sprite = ppu.line_sprites[0];
+            assert_eq!(sprite.row_addr, row_addr, "8x16 {}, flip {}, LY {}, tile {:#04x}", tall, flip, ly, tile);
+        }
+    }
+
+    #[test]
+    fn priority_between_sprites() {
+        // (CGB, X of the sprite 0, X of the sprite 1, sprite drawn at the screen X 13)
+        let cases = [
+            // DMG: the lowest X is on top
+            (false, 20, 15, 1),
+            (false, 15, 20, 0),
+            // The OAM index breaks the ties
+            (false, 20, 20, 0),
+            // CGB: the lowest OAM index is on top
+            (true, 20, 15, 0),
+            (true, 15, 20, 0),
+            (true, 20, 20, 0),
+        ];
+        for renderer in [Renderer::Scanline, Renderer::PixelFifo] {
+            for (cgb, x0, x1, on_top) in cases {
+                let mut ppu = Ppu::new(renderer);
+                ppu.cgb_mode = cgb;
+                ppu.obp0 = PALETTE;
+                // The sprite 0 has the color 1, the sprite 1 the color 2
+                fill_tile(&mut ppu, 0x10, 1);
+                fill_tile(&mut ppu, 0x20, 2);
+                set_sprite(&mut ppu, 0, 16, x0, 1, 0);
+                set_sprite(&mut ppu, 1, 16, x1, 2, 0);
+                let line = draw_first_line(&mut ppu, Lcdc::OBJ_ENABLE);
+                assert_eq!(line[13], on_top + 1, "{:?}, CGB {}, X {} and {}", renderer, cgb, x0, x1);
+            }
+        }
+    }
+
+    #[test]
+    fn background_over_sprite() {
+        // (BG color, attribute bit 7, shade expected)
+        let cases = [(0, false, 1), (0, true, 1), (3, false, 1), (3, true, 3), (1, true, 1)];
+        for renderer in [Renderer::Scanline, Renderer::PixelFifo] {
+            for (background, behind, expected) in cases {
+                let mut ppu = Ppu::new(renderer);
+                ppu.bgp = PALETTE;
+                ppu.obp0 = PALETTE;
+                // The background is made of the tile 0, the sprite is the tile 1
+                fill_tile(&mut ppu, 0x00, background);
+                fill_tile(&mut ppu, 0x10, 1);
+                set_sprite(&mut ppu, 0, 16, 8, 1, if behind { 0x80 } else { 0 });
+                let line = draw_first_line(&mut ppu, Lcdc::OBJ_ENABLE | Lcdc::BG_ENABLE | Lcdc::TILE_DATA);
+                assert_eq!(line[0], expected, "{:?}, BG color {}, behind {}", renderer, background, behind);
+                // Next to the sprite
+                assert_eq!(line[8], background, "{:?}, BG color {}, behind {}", renderer, background, behind);
+            }
+        }
+    }
+}
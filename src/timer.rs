@@ -0,0 +1,208 @@
+//! DIV, TIMA, TMA and TAC registers (0xFF04-0xFF07).
+
+pub const DIV_ADDR: u16 = 0xFF04;
+pub const TIMA_ADDR: u16 = 0xFF05;
+pub const TMA_ADDR: u16 = 0xFF06;
+pub const TAC_ADDR: u16 = 0xFF07;
+
+/// Reload of TIMA after an overflow, each state lasts one M-cycle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Reload {
+    #[default]
+    None,
+    /// TIMA overflowed and reads 0, writing it cancels the reload
+    Pending,
+    /// TIMA was just loaded with TMA, the writes to TIMA are ignored and the writes to TMA go
+    /// to TIMA too
+    Reloading,
+}
+
+/// The timer is driven by a 16 bits counter incremented every cycle, DIV is its upper byte and
+/// TIMA is incremented on the falling edge of the counter bit selected by TAC.
+#[derive(Clone, Debug, Default)]
+pub struct Timer {
+    counter: u16,
+    pub tima: u8,
+    pub tma: u8,
+    pub tac: u8,
+    reload: Reload,
+}
+
+impl Timer {
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            DIV_ADDR => (self.counter >> 8) as u8,
+            TIMA_ADDR => self.tima,
+            TMA_ADDR => self.tma,
+            TAC_ADDR => self.tac | 0xF8,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, val: u8) {
+        let before = self.input();
+        match addr {
+            // Any write resets the whole counter
+            DIV_ADDR => self.counter = 0,
+            TIMA_ADDR => match self.reload {
+                Reload::Pending => {
+                    self.reload = Reload::None;
+                    self.tima = val;
+                }
+                Reload::Reloading => {}
+                Reload::None => self.tima = val,
+            },
+            TMA_ADDR => {
+                self.tma = val;
+                if self.reload == Reload::Reloading {
+                    self.tima = val;
+                }
+            }
+            TAC_ADDR => self.tac = val & 0x07,
+            _ => {}
+        }
+        // Resetting DIV or changing TAC can produce a falling edge
+        if before && !self.input() {
+            self.increment();
+        }
+    }
+
+    /// Advance the timer, returns true when the timer interrupt must be requested.
+    pub fn tick(&mut self, cycles: u16) -> bool {
+        let mut interrupt = false;
+        for _ in 0..cycles / 4 {
+            // TMA is loaded and the interrupt requested one M-cycle after the overflow
+            match self.reload {
+                Reload::Pending => {
+                    self.tima = self.tma;
+                    self.reload = Reload::Reloading;
+                    interrupt = true;
+                }
+                Reload::Reloading => self.reload = Reload::None,
+                Reload::None => {}
+            }
+            let before = self.input();
+            self.counter = self.counter.wrapping_add(4);
+            if before && !self.input() {
+                self.increment();
+            }
+        }
+        interrupt
+    }
+
+    /// Set the internal counter, DIV is its upper byte.
+    pub fn set_counter(&mut self, counter: u16) {
+        self.counter = counter;
+    }
+
+    /// Signal watched for falling edges: the enable bit and the counter bit selected by TAC.
+    fn input(&self) -> bool {
+        let bit = match self.tac & 0x03 {
+            0 => 9,
+            1 => 3,
+            2 => 5,
+            _ => 7,
+        };
+        self.tac & 0x04 != 0 && self.counter & (1 << bit) != 0
+    }
+
+    fn increment(&mut self) {
+        let (tima, overflow) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        if overflow {
+            self.reload = Reload::Pending;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Timer enabled on counter bit 3, TIMA at 0xFF: it overflows in 16 cycles.
+    fn about_to_overflow() -> Timer {
+        let mut timer = Timer::default();
+        timer.write(TAC_ADDR, 0x05);
+        timer.write(TMA_ADDR, 0xAB);
+        timer.write(TIMA_ADDR, 0xFF);
+        timer
+    }
+
+    #[test]
+    fn overflow_reloads_tma_one_cycle_later() {
+        let mut timer = about_to_overflow();
+        assert!(!timer.tick(16));
+        assert_eq!(timer.read(TIMA_ADDR), 0x00);
+        assert!(timer.tick(4));
+        assert_eq!(timer.read(TIMA_ADDR), 0xAB);
+        assert!(!timer.tick(4));
+    }
+
+    #[test]
+    fn writes_around_the_reload() {
+        // (cycles run after the overflow, register written, value, TIMA expected, interrupt)
+        let cases = [
+            // The write to TIMA while it reads 0 cancels the reload and the interrupt
+            (0, TIMA_ADDR, 0x12, 0x12, false),
+            // The writes to TIMA in the cycle of the reload are ignored
+            (4, TIMA_ADDR, 0x12, 0xAB, true),
+            // The writes to TMA in the cycle of the reload go to TIMA too
+            (4, TMA_ADDR, 0x34, 0x34, true),
+            // After the reload the writes behave normally
+            (8, TIMA_ADDR, 0x12, 0x12, true),
+        ];
+        for (cycles, addr, val, tima, interrupt) in cases {
+            let mut timer = about_to_overflow();
+            timer.tick(16);
+            let mut requested = timer.tick(cycles);
+            timer.write(addr, val);
+            requested |= timer.tick(4);
+            assert_eq!(timer.read(TIMA_ADDR), tima, "write {:#04x} to {:#06x} after {} cycles", val, addr, cycles);
+            assert_eq!(requested, interrupt, "write {:#04x} to {:#06x} after {} cycles", val, addr, cycles);
+        }
+    }
+
+    #[test]
+    fn register_writes_falling_edges() {
+        // (counter, TAC, register written, value, TIMA increments)
+        let cases = [
+            // Resetting DIV while the selected bit is set is a falling edge
+            (0x0008, 0x05, DIV_ADDR, 0x00, 1),
+            (0x0004, 0x05, DIV_ADDR, 0x00, 0),
+            (0x0200, 0x04, DIV_ADDR, 0x00, 1),
+            // Only the selected bit matters
+            (0x01F8, 0x04, DIV_ADDR, 0x00, 0),
+            // Selecting a bit that is clear while the current one is set
+            (0x0008, 0x05, TAC_ADDR, 0x04, 1),
+            (0x0208, 0x05, TAC_ADDR, 0x04, 0),
+            // Disabling the timer while the selected bit is set
+            (0x0008, 0x05, TAC_ADDR, 0x01, 1),
+            (0x0000, 0x05, TAC_ADDR, 0x00, 0),
+        ];
+        for (counter, tac, addr, val, increments) in cases {
+            let mut timer = Timer::default();
+            timer.write(TAC_ADDR, tac);
+            timer.set_counter(counter);
+            timer.write(addr, val);
+            assert_eq!(
+                timer.read(TIMA_ADDR),
+                increments,
+                "counter {:#06x}, TAC {:#04x}, write {:#04x} to {:#06x}",
+                counter,
+                tac,
+                val,
+                addr
+            );
+        }
+    }
+
+    #[test]
+    fn div_write_overflow_requests_the_interrupt() {
+        let mut timer = about_to_overflow();
+        timer.set_counter(0x0008);
+        timer.write(DIV_ADDR, 0x00);
+        assert_eq!(timer.read(TIMA_ADDR), 0x00);
+        assert!(timer.tick(4));
+        assert_eq!(timer.read(TIMA_ADDR), 0xAB);
+    }
+}